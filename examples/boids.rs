@@ -1,10 +1,12 @@
 use bevy_ecs::{
     prelude::Component,
-    schedule::Schedule,
     system::{Query, ResMut},
     world::World,
 };
-use panda_bevy::{geometry::Circle, geometry::Vec2, canvas::Color, Canvas, Panda, PandaOptions};
+use panda_bevy::{
+    geometry::Circle, canvas::Color, scaling::{Scaling, ScalingMode}, Canvas, Panda,
+    PandaOptions,
+};
 use rand::random;
 
 const WIDTH: u32 = 480;
@@ -14,18 +16,20 @@ const NUM_BOIDS: u32 = 10;
 const RADIUS: f32 = 5.0;
 
 fn main() {
-    let mut panda = Panda::new(PandaOptions {
+    Panda::builder(PandaOptions {
         title: "boids!",
         width: WIDTH,
         height: HEIGHT,
         scale: 2,
-    });
-
-    spawn_boids(&mut panda.world);
-
-    let mut schedule = Schedule::new();
-    schedule.add_system(draw_boids);
-    panda.run(schedule);
+    })
+    // more room to fly in a bigger window
+    .insert_resource(Scaling {
+        mode: ScalingMode::Fill,
+        ..Scaling::default()
+    })
+    .add_startup_system(spawn_boids)
+    .add_render_system(draw_boids)
+    .run();
 }
 
 #[derive(Component)]
@@ -34,27 +38,21 @@ struct Boid {
     color: Color,
 }
 
-#[derive(Component)]
-struct Velocity(Vec2);
-
 fn spawn_boids(world: &mut World) {
     for _ in 0..NUM_BOIDS {
-        world.spawn((
-            Boid {
-                circle: Circle::new(
-                    random::<f32>() * WIDTH as f32,
-                    random::<f32>() * HEIGHT as f32,
-                    RADIUS,
-                ),
-                color: Color::new(
-                    random::<u8>(),
-                    random::<u8>(),
-                    random::<u8>(),
-                    random::<u8>(),
-                 ),
-            },
-            Velocity(Vec2::new(0.0, 0.0)),
-        ));
+        world.spawn(Boid {
+            circle: Circle::new(
+                random::<f32>() * WIDTH as f32,
+                random::<f32>() * HEIGHT as f32,
+                RADIUS,
+            ),
+            color: Color::new(
+                random::<u8>(),
+                random::<u8>(),
+                random::<u8>(),
+                random::<u8>(),
+            ),
+        });
     }
 }
 
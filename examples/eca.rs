@@ -1,6 +1,5 @@
 use bevy_ecs::{
     prelude::DetectChanges,
-    schedule::Schedule,
     system::{Res, ResMut, Resource},
     world::World,
 };
@@ -13,22 +12,19 @@ const GRID_HEIGHT: u32 = 16;
 const RULE: u8 = 110;
 
 fn main() {
-    let mut panda = Panda::new(PandaOptions {
+    Panda::builder(PandaOptions {
         width: GRID_WIDTH * CELL_SIZE as u32,
         height: GRID_HEIGHT * CELL_SIZE as u32,
         scale: 1,
         ..default()
-    });
-
-    setup(&mut panda.world);
-
-    let mut schedule = Schedule::new();
-    schedule.add_system(draw_grid);
-    panda.run(schedule);
+    })
+    .add_startup_system(setup)
+    .add_render_system(draw_grid)
+    .run();
 }
 
 fn setup(world: &mut World) {
-    let mut eca = ECA::new(GRID_WIDTH, GRID_HEIGHT, RULE);
+    let mut eca = Eca::new(GRID_WIDTH, GRID_HEIGHT, RULE);
     eca.grid.set(GRID_WIDTH as i32 - 1, 0, true);
     eca.update();
 
@@ -36,7 +32,7 @@ fn setup(world: &mut World) {
     Rule::new(10);
 }
 
-fn draw_grid(eca: Res<ECA>, mut canvas: ResMut<Canvas>) {
+fn draw_grid(eca: Res<Eca>, mut canvas: ResMut<Canvas>) {
     if eca.is_changed() {
         canvas.clear(Color::BLACK.pixel());
         for x in 0..eca.grid.width as i32 {
@@ -56,12 +52,12 @@ fn draw_grid(eca: Res<ECA>, mut canvas: ResMut<Canvas>) {
 }
 
 #[derive(Resource)]
-struct ECA {
+struct Eca {
     grid: Grid,
     rule: Rule,
 }
 
-impl ECA {
+impl Eca {
     fn new(width: u32, height: u32, rule: u8) -> Self {
         Self {
             grid: Grid::new(width, height),
@@ -102,7 +98,7 @@ impl Grid {
     fn get(&self, x: i32, y: i32) -> Option<&bool> {
         let x: usize = x.try_into().ok()?;
         let y: usize = y.try_into().ok()?;
-        Some(self.cells.get(y)?.get(x)?)
+        self.cells.get(y)?.get(x)
     }
 
     fn set(&mut self, x: i32, y: i32, alive: bool) -> Option<()> {
@@ -148,6 +144,7 @@ impl Rule {
 #[cfg(test)]
 mod test {
     use super::*;
+    use panda_bevy::{golden::assert_golden, Duration};
 
     #[test]
     fn grid_get() {
@@ -202,7 +199,7 @@ mod test {
 
     #[test]
     fn eca_update() {
-        let mut eca = ECA::new(16, 16, 110);
+        let mut eca = Eca::new(16, 16, 110);
         eca.grid.set(15, 0, true);
         eca.update();
 
@@ -223,5 +220,20 @@ mod test {
         assert_eq!(eca.grid.get(14, 15), Some(&false));
         assert_eq!(eca.grid.get(15, 15), Some(&true));
     }
-}
 
+    #[test]
+    fn eca_golden() {
+        let mut panda = Panda::builder(PandaOptions {
+            width: GRID_WIDTH * CELL_SIZE as u32,
+            height: GRID_HEIGHT * CELL_SIZE as u32,
+            ..default()
+        })
+        .add_startup_system(setup)
+        .add_render_system(draw_grid)
+        .build_headless();
+        panda.update(Duration::ZERO);
+
+        let canvas = panda.world.resource::<Canvas>();
+        assert_golden(canvas, concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/eca.png"), 0);
+    }
+}
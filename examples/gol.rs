@@ -1,26 +1,27 @@
-use bevy_ecs::{schedule::Schedule, system::{Resource, ResMut, Res, Local}, world::World};
-use panda_bevy::{Panda, PandaOptions, Canvas, canvas::Color, Time, Input, Key, utils::default};
+use bevy_ecs::{change_detection::DetectChanges, schedule::IntoSystemConfigs, system::{Resource, ResMut, Res}, world::World};
+use panda_bevy::{Panda, PandaOptions, Canvas, canvas::Color, FixedTime, Input, Key, MouseButton, Stage, utils::default};
+use panda_bevy::{sheet::{Grid, SpriteSheet}, sprite::Sprite, tilemap::{draw_tilemaps, Tile, Tilemap}};
 use rand::random;
 
 const GRID_WIDTH: usize = 200;
 const GRID_HEIGHT: usize = 200;
 
 const CELL_SIZE: i32 = 2;
-const TIME_STEP: f64 = 1.0 / 30.0;
+const TIME_STEP_HZ: f64 = 30.0;
 
 fn main() {
-    let mut panda = Panda::new(PandaOptions {
+    Panda::builder(PandaOptions {
         title: "gol!",
         width: GRID_WIDTH as u32 * CELL_SIZE as u32,
         height: GRID_HEIGHT as u32 * CELL_SIZE as u32,
         ..default()
-    });
-
-    setup(&mut panda.world);
-
-    let mut schedule = Schedule::new();
-    schedule.add_systems((draw_grid, step_board, randomize_board));
-    panda.run(schedule);
+    })
+    .insert_resource(FixedTime::from_hz(TIME_STEP_HZ))
+    .add_startup_system(setup)
+    .add_fixed_system(step_board)
+    .add_systems((randomize_board, paint_cells))
+    .add_systems_to(Stage::Render, (update_tiles, clear_canvas, draw_tilemaps).chain())
+    .run();
 }
 
 #[derive(Resource)]
@@ -54,7 +55,7 @@ impl Cells {
             let y = (i / GRID_WIDTH) as i32;
 
             let neighbors = self.count_neighbors(x, y);
-            if neighbors < 2 || neighbors > 3 { delta.push((i, false)) };
+            if !(2..=3).contains(&neighbors) { delta.push((i, false)) };
             if neighbors == 3 { delta.push((i, true)) };
         }
 
@@ -74,16 +75,18 @@ fn setup(world: &mut World) {
     let mut cells = Cells([false; GRID_WIDTH * GRID_HEIGHT]);
     cells.randomize();
     world.insert_resource(cells);
+
+    // every live cell is the same white square
+    let size = CELL_SIZE as u32;
+    let pixels = Color::WHITE.pixel().repeat((size * size) as usize);
+    let cell = Sprite::from_rgba(size, size, pixels).unwrap();
+    let mut tilemap = Tilemap::new(SpriteSheet::from_grid(cell, Grid::new(size, size)), size, size);
+    tilemap.add_layer("cells");
+    world.insert_resource(tilemap);
 }
 
-#[derive(Default)]
-struct Timer(f64);
-fn step_board(mut board: ResMut<Cells>, mut timer: Local<Timer>, time: Res<Time>) {
-    timer.0 += time.as_secs_f64(); 
-    if timer.0 > TIME_STEP {
-        timer.0 = 0.0;
-        board.step();
-    }
+fn step_board(mut board: ResMut<Cells>) {
+    board.step();
 }
 
 fn randomize_board(mut board: ResMut<Cells>, input: Res<Input>) {
@@ -92,20 +95,29 @@ fn randomize_board(mut board: ResMut<Cells>, input: Res<Input>) {
     }
 }
 
-fn draw_grid(board: Res<Cells>, mut canvas: ResMut<Canvas>) {
-    canvas.clear(Color::BLACK.pixel());
+// hold the left mouse button to bring cells to life
+fn paint_cells(mut board: ResMut<Cells>, input: Res<Input>) {
+    if !input.button_held(MouseButton::Left) { return };
+
+    if let Some(mouse) = input.mouse_canvas() {
+        let x = mouse.x as usize / CELL_SIZE as usize;
+        let y = mouse.y as usize / CELL_SIZE as usize;
+        board.0[y * GRID_WIDTH + x] = true;
+    }
+}
+
+fn update_tiles(board: Res<Cells>, mut tilemap: ResMut<Tilemap>) {
+    if !board.is_changed() { return };
+
+    let layer = &mut tilemap.layers[0];
+    layer.clear();
     for (i, &cell) in board.0.iter().enumerate() {
         if cell {
-            let x = i % GRID_WIDTH;
-            let y = i / GRID_WIDTH;
-            canvas.draw_rectangle(
-                x as i32 * CELL_SIZE,
-                y as i32 * CELL_SIZE,
-                CELL_SIZE,
-                CELL_SIZE,
-                Color::WHITE.pixel()
-             );
+            layer.set((i % GRID_WIDTH) as i32, (i / GRID_WIDTH) as i32, Tile::new(0));
         }
     }
 }
 
+fn clear_canvas(mut canvas: ResMut<Canvas>) {
+    canvas.clear(Color::BLACK.pixel());
+}
@@ -1,11 +1,16 @@
 use bevy_ecs::{
     prelude::DetectChanges,
-    schedule::{IntoSystemConfigs, Schedule},
+    schedule::{IntoSystemConfig, IntoSystemConfigs},
     system::{Res, ResMut, Resource},
     world::World,
 };
-use panda_bevy::{canvas::Color, Canvas, Input, Key, Panda, PandaOptions};
-use rand::{seq::SliceRandom, thread_rng};
+use panda_bevy::{
+    canvas::Color,
+    input::{Binding, InputMap, Recorder, Replay},
+    state::{in_state, NextState, State, States},
+    Canvas, Input, Key, Panda, PandaOptions, Stage,
+};
+use rand::{random, rngs::StdRng, seq::SliceRandom, SeedableRng};
 
 const CELL_SIZE: i32 = 10;
 const BOARD_HEIGHT: usize = 20;
@@ -17,19 +22,45 @@ const WIDTH: u32 = BOARD_WIDTH as u32 * CELL_SIZE as u32;
 const HEIGHT: u32 = BOARD_HEIGHT as u32 * CELL_SIZE as u32;
 
 fn main() {
-    let mut panda = Panda::new(PandaOptions {
+    let mut builder = Panda::builder(PandaOptions {
         title: "tetris!",
         width: WIDTH,
         height: HEIGHT,
         scale: 4,
     });
 
-    setup(&mut panda.world);
-
-    let mut scheulde = Schedule::new();
-    scheulde.add_systems((clear, draw_board, draw_ghost).chain());
-    scheulde.add_systems((move_x, turn_piece, hard_drop, extend_queue));
-    panda.run(scheulde);
+    // `--record game.json` saves a game when the window closes,
+    // `--replay game.json` plays one back
+    let args: Vec<String> = std::env::args().collect();
+    let seed = match args.get(1).map(String::as_str) {
+        Some("--record") => {
+            builder.insert_resource(Recorder::to_file(&args[2]));
+            0
+        }
+        Some("--replay") => {
+            builder.insert_resource(Replay::load(&args[2]).unwrap());
+            0
+        }
+        _ => random(),
+    };
+
+    builder
+        .insert_resource(Queue::new(seed))
+        .add_startup_system(setup)
+        .add_state::<Game>()
+        .add_exit_system(Game::Over, restart)
+        .add_systems(
+            (move_x, turn_piece, hard_drop, extend_queue)
+                .distributive_run_if(in_state(Game::Playing)),
+        )
+        .add_system(pause)
+        .add_system(play_again.run_if(in_state(Game::Over)))
+        // the ghost hides while paused and after the game is over
+        .add_systems_to(
+            Stage::Render,
+            (clear, draw_board, draw_ghost.run_if(in_state(Game::Playing))).chain(),
+        )
+        .run();
 }
 
 #[derive(Copy, Clone)]
@@ -142,7 +173,7 @@ impl Board {
 
         cells
             .iter()
-            .all(|cell| if let Cell::Empty = cell { true } else { false })
+            .all(|cell| matches!(cell, Cell::Empty))
     }
 
     fn where_insert(&self, tetrimino: &Tetrimino, center_x: i32) -> i32 {
@@ -163,13 +194,20 @@ impl Board {
     }
 }
 
-#[derive(Resource, Default)]
-struct Queue(Vec<Tetrimino>);
+// pieces come from a seeded rng so replays deal the same ones
+#[derive(Resource)]
+struct Queue(Vec<Tetrimino>, StdRng);
 
 impl Queue {
+    fn new(seed: u64) -> Self {
+        let mut queue = Queue(Vec::new(), StdRng::seed_from_u64(seed));
+        queue.extend();
+        queue
+    }
+
     fn extend(&mut self) {
         let mut indexes = [0, 1, 2, 3, 4, 5, 6];
-        indexes.shuffle(&mut thread_rng());
+        indexes.shuffle(&mut self.1);
 
         self.0.append(
             &mut indexes
@@ -182,23 +220,84 @@ impl Queue {
 
 fn setup(world: &mut World) {
     world.insert_resource(Board::EMPTY);
+    world.insert_resource(CenterX(3)); // ??
+    world.insert_resource(controls());
+}
 
-    let mut queue = Queue::default();
-    queue.extend();
-    world.insert_resource(queue);
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Game {
+    #[default]
+    Playing,
+    Paused,
+    Over,
+}
 
-    world.insert_resource(CenterX(3)); // ??
+fn pause(
+    input: Res<Input>,
+    controls: Res<InputMap<Control>>,
+    game: Res<State<Game>>,
+    mut next: ResMut<NextState<Game>>,
+) {
+    if controls.pressed(&input, Control::Pause) {
+        match game.0 {
+            Game::Playing => next.set(Game::Paused),
+            Game::Paused => next.set(Game::Playing),
+            Game::Over => {}
+        }
+    }
+}
+
+fn play_again(
+    input: Res<Input>,
+    controls: Res<InputMap<Control>>,
+    mut next: ResMut<NextState<Game>>,
+) {
+    if controls.pressed(&input, Control::Drop) {
+        next.set(Game::Playing);
+    }
+}
+
+fn restart(mut board: ResMut<Board>, mut center_x: ResMut<CenterX>) {
+    *board = Board::EMPTY;
+    center_x.0 = 3;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+enum Control {
+    Left,
+    Right,
+    TurnLeft,
+    TurnRight,
+    Drop,
+    Pause,
+}
+
+// players can change these in controls.json
+fn controls() -> InputMap<Control> {
+    InputMap::load("controls.json").unwrap_or_else(|_| {
+        InputMap::new()
+            .with(Control::Left, Binding::Key(Key::Left))
+            .with(Control::Right, Binding::Key(Key::Right))
+            .with(Control::TurnLeft, Binding::Key(Key::Z))
+            .with(Control::TurnRight, Binding::Key(Key::Up))
+            .with(Control::Drop, Binding::Key(Key::Space))
+            .with(Control::Pause, Binding::Key(Key::P))
+    })
 }
 
 #[derive(Resource)]
 struct CenterX(i32);
 
-fn move_x(mut center_x: ResMut<CenterX>, input: Res<Input>) {
-    if input.key_pressed(Key::Left) {
+fn move_x(
+    mut center_x: ResMut<CenterX>,
+    input: Res<Input>,
+    controls: Res<InputMap<Control>>,
+) {
+    if controls.pressed(&input, Control::Left) {
         center_x.0 -= if center_x.0 > 0 { 1 } else { 0 };
     }
 
-    if input.key_pressed(Key::Right) {
+    if controls.pressed(&input, Control::Right) {
         center_x.0 += if center_x.0 < BOARD_WIDTH as i32 {
             1
         } else {
@@ -207,14 +306,18 @@ fn move_x(mut center_x: ResMut<CenterX>, input: Res<Input>) {
     }
 }
 
-fn turn_piece(mut queue: ResMut<Queue>, input: Res<Input>) {
-    if input.key_pressed(Key::Z) {
+fn turn_piece(
+    mut queue: ResMut<Queue>,
+    input: Res<Input>,
+    controls: Res<InputMap<Control>>,
+) {
+    if controls.pressed(&input, Control::TurnLeft) {
         if let Some(tetrimino) = queue.0.first_mut() {
             tetrimino.turn_left();
         }
     }
 
-    if input.key_pressed(Key::Up) {
+    if controls.pressed(&input, Control::TurnRight) {
         if let Some(tetrimino) = queue.0.first_mut() {
             tetrimino.turn_right();
         }
@@ -226,10 +329,17 @@ fn hard_drop(
     mut queue: ResMut<Queue>,
     mut center_x: ResMut<CenterX>,
     input: Res<Input>,
+    controls: Res<InputMap<Control>>,
+    mut next: ResMut<NextState<Game>>,
 ) {
-    if input.key_pressed(Key::Space) {
+    if controls.pressed(&input, Control::Drop) {
         let tetrimino = &queue.0[0];
-        board.insert(&tetrimino, center_x.0);
+        // no room left under the top
+        if board.where_insert(tetrimino, center_x.0) < 1 {
+            next.set(Game::Over);
+            return;
+        }
+        board.insert(tetrimino, center_x.0);
         center_x.0 = 3;
         queue.0.remove(0);
     }
@@ -267,7 +377,7 @@ fn draw_ghost(
     queue: Res<Queue>,
 ) {
     if let Some(tetrimino) = queue.0.first() {
-        let center_y = board.where_insert(&tetrimino, center_x.0);
+        let center_y = board.where_insert(tetrimino, center_x.0);
         for Point(x, y) in &tetrimino.points {
             canvas.draw_rectangle(
                 (center_x.0 + *x) * CELL_SIZE,
@@ -1,43 +1,54 @@
 use bevy_ecs::{
     prelude::Component,
-    schedule::Schedule,
+    schedule::IntoSystemConfigs,
     system::{Query, Res, ResMut},
     world::World, query::With,
 };
 use panda_bevy::{
-    geometry::Vec2, canvas::Color, sprite::Sprite, Canvas, Input, Key, Panda, PandaOptions, Time,
+    canvas::Color,
+    geometry::Vec2,
+    sprite::{DrawParams, Sprite},
+    Canvas, FixedTime, Input, Key, Panda, PandaBuilder, PandaOptions, Stage,
 };
 
 const WIDTH: u32 = 160;
 const HEIGHT: u32 = 144;
 
 fn main() {
-    let mut panda = Panda::new(PandaOptions {
+    vespa(PandaOptions {
         title: "vespa!",
         width: WIDTH,
         height: HEIGHT,
         scale: 4,
-    });
-
-    setup(&mut panda.world);
+    })
+    .run();
+}
 
-    let mut schedule = Schedule::new();
-    schedule.add_systems((
-        apply_velocity,
-        apply_gravity,
-        ground_collision,
-        player_control,
-        draw_sprites,
-    ));
-    panda.run(schedule);
+// physics steps at a fixed rate so jumps are the same height at any frame rate
+fn vespa(options: PandaOptions) -> PandaBuilder {
+    let mut builder = Panda::builder(options);
+    builder
+        .add_startup_system(setup)
+        .add_system(player_control)
+        .add_systems_to(
+            Stage::FixedUpdate,
+            (apply_velocity, apply_gravity, ground_collision).chain(),
+        )
+        .add_render_system(draw_sprites);
+    builder
 }
 
 #[derive(Component)]
 struct Position(Vec2);
+// where it was before the last fixed update, for drawing in between
+#[derive(Component)]
+struct Previous(Vec2);
 #[derive(Component)]
 struct Velocity(Vec2);
 #[derive(Component)]
 struct Drawable(Sprite);
+#[derive(Component, Default)]
+struct Flipped(bool);
 
 #[derive(Component)]
 struct Player;
@@ -46,21 +57,27 @@ fn setup(world: &mut World) {
     world.spawn((
         Player,
         Position(Vec2::new(30.0, 30.0)),
+        Previous(Vec2::new(30.0, 30.0)),
         Velocity(Vec2::new(0.0, 0.0)),
-        Drawable(Sprite::new("./assets/vespa.png")),
+        Flipped::default(),
+        Drawable(Sprite::from_bytes(include_bytes!("../assets/vespa.png")).unwrap()),
     ));
 }
 
-fn apply_velocity(mut query: Query<(&mut Position, &Velocity)>, time: Res<Time>) {
-    for (mut position, velocity) in &mut query {
-        position.0 += velocity.0 * time.as_secs_f32();
+fn apply_velocity(
+    mut query: Query<(&mut Position, &mut Previous, &Velocity)>,
+    time: Res<FixedTime>,
+) {
+    for (mut position, mut previous, velocity) in &mut query {
+        previous.0 = position.0;
+        position.0 += velocity.0 * time.step().as_secs_f32();
     }
 }
 
 const GRAVITY: Vec2 = Vec2::new(0.0, 150.0);
-fn apply_gravity(mut query: Query<&mut Velocity>, time: Res<Time>) {
+fn apply_gravity(mut query: Query<&mut Velocity>, time: Res<FixedTime>) {
     for mut velocity in &mut query {
-        velocity.0 += GRAVITY * time.as_secs_f32();
+        velocity.0 += GRAVITY * time.step().as_secs_f32();
     }
 }
 
@@ -73,11 +90,25 @@ fn ground_collision(mut query: Query<(&mut Position, &mut Velocity, &Drawable)>)
     }
 }
 
-const JUMP: Vec2 = Vec2::new(0.0, -70.0);
-fn player_control(mut query: Query<&mut Velocity, With<Player>>, input: Res<Input>) {
-    for mut velocity in &mut query {
+const JUMP: f32 = -70.0;
+const WALK: f32 = 40.0;
+fn player_control(
+    mut query: Query<(&mut Velocity, &mut Flipped), With<Player>>,
+    input: Res<Input>,
+) {
+    for (mut velocity, mut flipped) in &mut query {
         if input.key_pressed(Key::Space) {
-            velocity.0 = JUMP;
+            velocity.0.y = JUMP;
+        }
+
+        velocity.0.x = 0.0;
+        if input.key_held(Key::Left) {
+            velocity.0.x -= WALK;
+            flipped.0 = true;
+        }
+        if input.key_held(Key::Right) {
+            velocity.0.x += WALK;
+            flipped.0 = false;
         }
     }
 }
@@ -89,9 +120,78 @@ const SKY: Color = Color {
     a: 255,
 };
 
-fn draw_sprites(query: Query<(&Drawable, &Position)>, mut canvas: ResMut<Canvas>) {
+fn draw_sprites(
+    query: Query<(&Drawable, &Position, &Previous, &Flipped)>,
+    mut canvas: ResMut<Canvas>,
+    time: Res<FixedTime>,
+) {
     canvas.clear(SKY.pixel());
-    for (drawable, position) in &query {
-        drawable.0.draw(&mut canvas, &position.0);
+    for (drawable, position, previous, flipped) in &query {
+        let position = previous.0.lerp(position.0, time.alpha());
+        let params = DrawParams {
+            flip_x: flipped.0,
+            ..DrawParams::default()
+        };
+        drawable.0.draw_with(&mut canvas, &position, &params);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use panda_bevy::{utils::default, Duration};
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    fn player_y(panda: &mut Panda) -> f32 {
+        let mut query = panda.world.query_filtered::<&Position, With<Player>>();
+        query.single(&panda.world).0.y
+    }
+
+    #[test]
+    fn player_jumps() {
+        let mut panda = vespa(PandaOptions {
+            width: WIDTH,
+            height: HEIGHT,
+            ..default()
+        })
+        .build_headless();
+
+        panda.update_frames(120, FRAME);
+        let ground = player_y(&mut panda);
+        assert!((ground - (HEIGHT - 38) as f32).abs() < 1.0);
+
+        panda.press_key(Key::Space);
+        panda.update_frames(10, FRAME);
+        assert!(player_y(&mut panda) < ground - 5.0);
+
+        panda.update_frames(120, FRAME);
+        assert!((player_y(&mut panda) - ground).abs() < 1.0);
+    }
+
+    // the highest the player gets after one jump
+    fn jump_peak(fps: u32) -> f32 {
+        let mut panda = vespa(PandaOptions {
+            width: WIDTH,
+            height: HEIGHT,
+            ..default()
+        })
+        .build_headless();
+
+        let frame = Duration::from_secs(1) / fps;
+        panda.update_frames(fps * 2, frame);
+
+        panda.press_key(Key::Space);
+        let mut peak = f32::INFINITY;
+        for _ in 0..fps * 2 {
+            panda.update(frame);
+            peak = peak.min(player_y(&mut panda));
+        }
+        peak
+    }
+
+    #[test]
+    fn jump_height_ignores_frame_rate() {
+        assert_eq!(jump_peak(30), jump_peak(144));
     }
 }
@@ -0,0 +1,263 @@
+// flipbook animation over the frames of a `SpriteSheet`
+
+use bevy_ecs::{
+    prelude::Component,
+    system::{Query, Res},
+};
+
+use crate::{
+    app::{PandaBuilder, Plugin},
+    Duration, Time,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    // forwards, backwards, forwards..
+    PingPong,
+    // stops on the last frame
+    Once,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    // indices into a sprite sheet
+    pub frames: Vec<usize>,
+    // how long each frame shows for
+    pub durations: Vec<Duration>,
+    pub mode: AnimationMode,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<usize>, duration: Duration, mode: AnimationMode) -> Self {
+        let durations = vec![duration; frames.len()];
+        Self::with_durations(frames, durations, mode)
+    }
+
+    pub fn with_durations(
+        frames: Vec<usize>,
+        durations: Vec<Duration>,
+        mode: AnimationMode,
+    ) -> Self {
+        assert_eq!(
+            frames.len(),
+            durations.len(),
+            "every frame needs a duration"
+        );
+
+        // zero length frames would never let a looping animation finish advancing
+        let durations = durations
+            .into_iter()
+            .map(|duration| duration.max(Duration::from_millis(1)))
+            .collect();
+
+        Self {
+            frames,
+            durations,
+            mode,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.durations.iter().sum()
+    }
+}
+
+// fast enough for anything, without one long frame looping through the animation forever
+const MAX_SPEED: f32 = 1000.0;
+
+#[derive(Component, Clone, Debug)]
+pub struct AnimationPlayer {
+    animation: Animation,
+    // position in `animation.frames`
+    index: usize,
+    elapsed: Duration,
+    forwards: bool,
+    finished: bool,
+    just_finished: bool,
+    // 2.0 plays twice as fast, kept between 0 and `MAX_SPEED`, nan and infinity stop it
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            index: 0,
+            elapsed: Duration::ZERO,
+            forwards: true,
+            finished: false,
+            just_finished: false,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    // switch to another animation from its first frame
+    pub fn play(&mut self, animation: Animation) {
+        *self = Self {
+            speed: self.speed,
+            ..Self::new(animation)
+        };
+    }
+
+    pub fn restart(&mut self) {
+        let animation = self.animation.clone();
+        self.play(animation);
+    }
+
+    // the sprite sheet frame to draw right now
+    pub fn frame(&self) -> usize {
+        self.animation.frames.get(self.index).copied().unwrap_or(0)
+    }
+
+    // only `Once` animations ever finish
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    // true for the one frame the animation finished on
+    pub fn just_finished(&self) -> bool {
+        self.just_finished
+    }
+
+    pub fn advance(&mut self, delta: Duration) {
+        self.just_finished = false;
+        if self.finished || self.paused || self.animation.frames.is_empty() {
+            return;
+        }
+
+        let speed = match self.speed.is_finite() {
+            true => self.speed.clamp(0.0, MAX_SPEED),
+            false => 0.0,
+        };
+        let scaled = Duration::try_from_secs_f64(delta.as_secs_f64() * speed as f64);
+        self.elapsed = self.elapsed.saturating_add(scaled.unwrap_or(Duration::MAX));
+        while self.elapsed >= self.animation.durations[self.index] {
+            self.elapsed -= self.animation.durations[self.index];
+
+            if !self.next() {
+                self.elapsed = Duration::ZERO;
+                self.finished = true;
+                self.just_finished = true;
+                break;
+            }
+        }
+    }
+
+    // returns false when there's no next frame
+    fn next(&mut self) -> bool {
+        let last = self.animation.frames.len() - 1;
+        match self.animation.mode {
+            AnimationMode::Loop => self.index = (self.index + 1) % (last + 1),
+            AnimationMode::Once if self.index == last => return false,
+            AnimationMode::Once => self.index += 1,
+            AnimationMode::PingPong if last == 0 => {}
+            AnimationMode::PingPong => {
+                if self.index == last {
+                    self.forwards = false;
+                } else if self.index == 0 {
+                    self.forwards = true;
+                }
+
+                if self.forwards {
+                    self.index += 1;
+                } else {
+                    self.index -= 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+// adds `animate`
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, builder: &mut PandaBuilder) {
+        builder.add_system(animate);
+    }
+}
+
+// advances every `AnimationPlayer` by the frame's delta
+pub fn animate(mut query: Query<&mut AnimationPlayer>, time: Res<Time>) {
+    for mut player in &mut query {
+        player.advance(**time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn frames(player: &mut AnimationPlayer, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                player.advance(FRAME);
+                player.frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn animation_loop() {
+        let animation = Animation::new(vec![4, 5, 6], FRAME, AnimationMode::Loop);
+        let mut player = AnimationPlayer::new(animation);
+        assert_eq!(player.frame(), 4);
+        assert_eq!(frames(&mut player, 4), [5, 6, 4, 5]);
+        assert!(!player.finished());
+    }
+
+    #[test]
+    fn animation_ping_pong() {
+        let animation = Animation::new(vec![0, 1, 2], FRAME, AnimationMode::PingPong);
+        let mut player = AnimationPlayer::new(animation);
+        assert_eq!(frames(&mut player, 6), [1, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn animation_speed() {
+        let animation = Animation::new(vec![0, 1, 2], FRAME, AnimationMode::Loop);
+        let mut player = AnimationPlayer::new(animation);
+        for speed in [f32::INFINITY, f32::NAN, -2.0] {
+            player.speed = speed;
+            assert_eq!(frames(&mut player, 2), [0, 0]);
+        }
+
+        // capped instead of overflowing
+        player.speed = f32::MAX;
+        player.advance(Duration::from_millis(1));
+        assert_eq!(player.frame(), 1);
+    }
+
+    #[test]
+    fn animation_once() {
+        let durations = vec![FRAME, FRAME * 3];
+        let animation = Animation::with_durations(vec![7, 8], durations, AnimationMode::Once);
+        let mut player = AnimationPlayer::new(animation);
+
+        player.advance(FRAME * 2);
+        assert_eq!(player.frame(), 8);
+        assert!(!player.finished());
+
+        player.advance(FRAME * 2);
+        assert!(player.finished() && player.just_finished());
+        assert_eq!(player.frame(), 8);
+
+        player.advance(FRAME);
+        assert!(player.finished() && !player.just_finished());
+
+        player.restart();
+        assert_eq!(player.frame(), 7);
+        assert!(!player.finished());
+    }
+}
@@ -0,0 +1,384 @@
+// build a panda out of systems and resources, and the stages it runs them in
+
+use std::{any::TypeId, collections::HashSet};
+
+use bevy_ecs::{
+    event::Event,
+    schedule::{IntoSystemConfig, IntoSystemConfigs, Schedule, ScheduleLabel, Schedules},
+    system::Resource,
+    world::{FromWorld, World},
+};
+
+use crate::{
+    create_world, error, events, input,
+    state::{self, OnEnter, OnExit, States},
+    FixedTime, Panda, PandaError, PandaOptions, Time,
+};
+
+const BUILT: &str = "this builder already made its panda, start another with `Panda::builder`";
+
+// every stage is a schedule in the world's `Schedules`
+#[derive(ScheduleLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    // once, before the first frame, after the window and canvas exist
+    Startup,
+    // as many times as fit in the frame, see `FixedTime`
+    FixedUpdate,
+    Update,
+    // after `Update`, for drawing to the canvas
+    Render,
+    // once, when the window closes
+    Teardown,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Startup,
+        Stage::FixedUpdate,
+        Stage::Update,
+        Stage::Render,
+        Stage::Teardown,
+    ];
+}
+
+// a bundle of systems and resources to drop into any panda
+pub trait Plugin: 'static {
+    fn build(&self, builder: &mut PandaBuilder);
+}
+
+// plain functions make quick plugins
+impl<F: Fn(&mut PandaBuilder) + 'static> Plugin for F {
+    fn build(&self, builder: &mut PandaBuilder) {
+        self(builder)
+    }
+}
+
+pub struct PandaBuilder<'a> {
+    options: PandaOptions<'a>,
+    // gone once the panda is built
+    world: Option<World>,
+    plugins: HashSet<TypeId>,
+}
+
+impl Panda {
+    pub fn builder(options: PandaOptions) -> PandaBuilder {
+        PandaBuilder {
+            world: Some(create_world(&options)),
+            options,
+            plugins: HashSet::new(),
+        }
+    }
+}
+
+impl<'a> PandaBuilder<'a> {
+    pub fn world(&mut self) -> &mut World {
+        self.world.as_mut().expect(BUILT)
+    }
+
+    // plugins are only built once, so they can add the plugins they need themselves
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        if self.plugins.insert(TypeId::of::<P>()) {
+            plugin.build(self);
+        }
+        self
+    }
+
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.plugins.contains(&TypeId::of::<P>())
+    }
+
+    // `Events<E>` for systems to send and read, each event lasts until the end of the next frame
+    pub fn add_event<E: Event>(&mut self) -> &mut Self {
+        events::add_event::<E>(self.world());
+        self
+    }
+
+    // a `State<S>` starting at `S::default()`, change it with `NextState<S>`
+    pub fn add_state<S: States>(&mut self) -> &mut Self {
+        state::add_state::<S>(self.world());
+        self
+    }
+
+    // runs once each time `state` is entered, including the first state after startup
+    pub fn add_enter_system<S: States, M>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemConfig<M>,
+    ) -> &mut Self {
+        self.add_state::<S>();
+        self.schedules()
+            .get_mut(&OnEnter(state))
+            .unwrap()
+            .add_system(system);
+        self
+    }
+
+    pub fn add_exit_system<S: States, M>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemConfig<M>,
+    ) -> &mut Self {
+        self.add_state::<S>();
+        self.schedules()
+            .get_mut(&OnExit(state))
+            .unwrap()
+            .add_system(system);
+        self
+    }
+
+    pub fn insert_resource(&mut self, resource: impl Resource) -> &mut Self {
+        self.world().insert_resource(resource);
+        self
+    }
+
+    pub fn init_resource<R: Resource + FromWorld>(&mut self) -> &mut Self {
+        self.world().init_resource::<R>();
+        self
+    }
+
+    pub fn schedule_mut(&mut self, stage: Stage) -> &mut Schedule {
+        self.schedules()
+            .get_mut(&stage)
+            .expect("every stage has a schedule")
+    }
+
+    fn schedules(&mut self) -> &mut Schedules {
+        self.world().resource_mut::<Schedules>().into_inner()
+    }
+
+    pub fn add_systems_to<M>(
+        &mut self,
+        stage: Stage,
+        systems: impl IntoSystemConfigs<M>,
+    ) -> &mut Self {
+        self.schedule_mut(stage).add_systems(systems);
+        self
+    }
+
+    pub fn add_startup_system<M>(&mut self, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule_mut(Stage::Startup).add_system(system);
+        self
+    }
+
+    pub fn add_system<M>(&mut self, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule_mut(Stage::Update).add_system(system);
+        self
+    }
+
+    pub fn add_systems<M>(&mut self, systems: impl IntoSystemConfigs<M>) -> &mut Self {
+        self.add_systems_to(Stage::Update, systems)
+    }
+
+    pub fn add_fixed_system<M>(&mut self, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule_mut(Stage::FixedUpdate).add_system(system);
+        self
+    }
+
+    pub fn add_render_system<M>(&mut self, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule_mut(Stage::Render).add_system(system);
+        self
+    }
+
+    pub fn add_teardown_system<M>(&mut self, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule_mut(Stage::Teardown).add_system(system);
+        self
+    }
+
+    // opens the window, the builder can't be used after this or any other way of building
+    pub fn build(&mut self) -> Panda {
+        self.try_build().expect("couldn't open the panda")
+    }
+
+    // an `ErrorHook` inserted first gets asked about surface errors, and can fall back
+    pub fn try_build(&mut self) -> Result<Panda, PandaError> {
+        let (options, world) = self.take();
+        Panda::with_world(options, world)
+    }
+
+    pub fn build_headless(&mut self) -> Panda {
+        self.try_build_headless().expect("couldn't make the panda")
+    }
+
+    pub fn try_build_headless(&mut self) -> Result<Panda, PandaError> {
+        let (options, world) = self.take();
+        Panda::headless_with_world(options, world)
+    }
+
+    pub fn run(&mut self) {
+        self.build().start()
+    }
+
+    fn take(&mut self) -> (PandaOptions<'a>, World) {
+        (
+            std::mem::take(&mut self.options),
+            self.world.take().expect(BUILT),
+        )
+    }
+}
+
+pub(crate) fn add_stages(world: &mut World) {
+    let mut schedules = Schedules::new();
+    for stage in Stage::ALL {
+        schedules.insert(stage, Schedule::new());
+    }
+    world.insert_resource(schedules);
+}
+
+// swaps `schedule` in for `stage`, handing back what was there
+pub(crate) fn replace_stage(world: &mut World, stage: Stage, schedule: Schedule) -> Schedule {
+    world
+        .resource_mut::<Schedules>()
+        .insert(stage, schedule)
+        .unwrap_or_default()
+}
+
+// the end of a panda, whichever way it stops, only the first time
+pub(crate) fn stop(world: &mut World, stopped: &mut bool) {
+    if !*stopped {
+        world.run_schedule(Stage::Teardown);
+        if let Err(why) = input::finish_recording(world) {
+            error::handle_error(world, &PandaError::Recording(why));
+        }
+        *stopped = true;
+    }
+}
+
+// one frame, once `Input` and `Time` are up to date
+pub(crate) fn run_frame(world: &mut World, started: &mut bool) {
+    if !*started {
+        world.run_schedule(Stage::Startup);
+        state::enter_states(world);
+        *started = true;
+    }
+    state::apply_transitions(world);
+
+    let delta = **world.resource::<Time>();
+    for _ in 0..world.resource_mut::<FixedTime>().tick(delta) {
+        world.run_schedule(Stage::FixedUpdate);
+    }
+    world.run_schedule(Stage::Update);
+    world.run_schedule(Stage::Render);
+    events::update_events(world);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::system::{Res, ResMut};
+
+    use super::*;
+    use crate::{Canvas, Duration};
+
+    #[derive(Resource, Default)]
+    struct Log(Vec<&'static str>);
+
+    #[derive(Resource)]
+    struct Size(u32);
+
+    struct LogPlugin(&'static str);
+
+    impl Plugin for LogPlugin {
+        fn build(&self, builder: &mut PandaBuilder) {
+            let message = self.0;
+            builder
+                .init_resource::<Log>()
+                .add_system(move |mut log: ResMut<Log>| log.0.push(message));
+        }
+    }
+
+    fn startup(mut log: ResMut<Log>, canvas: Res<Canvas>, mut size: ResMut<Size>) {
+        log.0.push("startup");
+        size.0 = canvas.width();
+    }
+
+    #[test]
+    fn builder_stages() {
+        let mut panda = Panda::builder(PandaOptions {
+            width: 40,
+            ..PandaOptions::default()
+        })
+        .init_resource::<Log>()
+        .insert_resource(Size(0))
+        .insert_resource(FixedTime::new(Duration::from_millis(10)))
+        .add_render_system(|mut log: ResMut<Log>| log.0.push("render"))
+        .add_system(|mut log: ResMut<Log>| log.0.push("update"))
+        .add_fixed_system(|mut log: ResMut<Log>| log.0.push("fixed"))
+        .add_startup_system(startup)
+        .add_teardown_system(|mut log: ResMut<Log>| log.0.push("teardown"))
+        .build_headless();
+
+        panda.update(Duration::from_millis(25));
+        panda.update(Duration::from_millis(5));
+        panda.teardown();
+        panda.teardown();
+
+        assert_eq!(
+            panda.world.resource::<Log>().0,
+            [
+                "startup", "fixed", "fixed", "update", "render", "fixed", "update", "render",
+                "teardown"
+            ]
+        );
+        assert_eq!(panda.world.resource::<Size>().0, 40);
+    }
+
+    #[test]
+    fn no_frames_after_teardown() {
+        let mut panda = Panda::builder(PandaOptions::default())
+            .init_resource::<Log>()
+            .add_system(|mut log: ResMut<Log>| log.0.push("update"))
+            .add_render_system(|mut log: ResMut<Log>| log.0.push("render"))
+            .add_teardown_system(|mut log: ResMut<Log>| log.0.push("teardown"))
+            .build_headless();
+
+        panda.update(Duration::ZERO);
+        panda.teardown();
+        panda.update_frames(2, Duration::ZERO);
+        panda.teardown();
+        assert_eq!(
+            panda.world.resource::<Log>().0,
+            ["update", "render", "teardown"]
+        );
+    }
+
+    #[test]
+    fn builder_step_swaps_update() {
+        let mut panda = Panda::builder(PandaOptions::default())
+            .init_resource::<Log>()
+            .add_system(|mut log: ResMut<Log>| log.0.push("update"))
+            .build_headless();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(|mut log: ResMut<Log>| log.0.push("step"));
+        panda.step(&mut schedule, Duration::from_millis(16));
+        panda.update(Duration::from_millis(16));
+        assert_eq!(panda.world.resource::<Log>().0, ["step", "update"]);
+    }
+
+    #[test]
+    fn builder_plugins() {
+        fn render(builder: &mut PandaBuilder) {
+            builder
+                .add_plugin(LogPlugin("update"))
+                .add_render_system(|mut log: ResMut<Log>| log.0.push("render"));
+        }
+
+        let mut builder = Panda::builder(PandaOptions::default());
+        builder
+            .add_plugin(LogPlugin("update"))
+            .add_plugin(render)
+            // already added, so skipped
+            .add_plugin(LogPlugin("again"));
+        assert!(builder.has_plugin::<LogPlugin>());
+
+        let mut panda = builder.build_headless();
+        panda.update(Duration::ZERO);
+        assert_eq!(panda.world.resource::<Log>().0, ["update", "render"]);
+    }
+    #[test]
+    #[should_panic(expected = "already made its panda")]
+    fn builder_used_after_build() {
+        let mut builder = Panda::builder(PandaOptions::default());
+        builder.build_headless();
+        builder.add_system(|| {});
+    }
+}
@@ -0,0 +1,821 @@
+// loader for aseprite's binary .ase/.aseprite files
+// https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+
+use std::{fmt, fs, io, path::Path};
+
+use miniz_oxide::inflate::decompress_to_vec_zlib_with_limit;
+
+use crate::{
+    animation::{Animation, AnimationMode},
+    blend::BlendMode,
+    sheet::{Grid, SpriteSheet},
+    sprite::Sprite,
+    Canvas, Duration,
+};
+
+pub struct Aseprite {
+    pub width: u32,
+    pub height: u32,
+    // every frame with its visible layers flattened
+    pub frames: Vec<Sprite>,
+    pub durations: Vec<Duration>,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    // inclusive
+    pub from: usize,
+    pub to: usize,
+    pub direction: Direction,
+    // how many times to play, 0 is forever
+    pub repeat: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+// aseprite goes up to 65535x65535, but every frame gets flattened into its own sprite and
+// then side by side into one sheet, so files with more pixels than this across all their
+// frames are turned away before anything big is allocated
+const MAX_PIXELS: u64 = 1 << 26;
+
+#[derive(Debug)]
+pub enum AsepriteError {
+    Io(io::Error),
+    // not an aseprite file, or a broken one
+    Invalid(String),
+}
+
+impl Aseprite {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, AsepriteError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AsepriteError> {
+        Ok(File::parse(bytes)?.flatten())
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    // a tag's frames and timing, indices line up with `sprite_sheet`
+    pub fn animation(&self, name: &str) -> Option<Animation> {
+        let tag = self.tag(name)?;
+        let mut frames: Vec<usize> =
+            (tag.from..=tag.to.min(self.frames.len().saturating_sub(1))).collect();
+        if let Direction::Reverse | Direction::PingPongReverse = tag.direction {
+            frames.reverse();
+        }
+
+        let mode = match (tag.direction, tag.repeat) {
+            (Direction::PingPong | Direction::PingPongReverse, 0) => AnimationMode::PingPong,
+            // each way counts as a repeat, turning around on the end frames without repeating them
+            (Direction::PingPong | Direction::PingPongReverse, repeat) => {
+                let mut pass = frames.clone();
+                for _ in 1..repeat {
+                    pass.reverse();
+                    frames.extend(pass.iter().skip(1));
+                }
+                AnimationMode::Once
+            }
+            (_, 0) => AnimationMode::Loop,
+            (_, repeat) => {
+                frames = frames.repeat(repeat as usize);
+                AnimationMode::Once
+            }
+        };
+
+        let durations = frames.iter().map(|&frame| self.durations[frame]).collect();
+        Some(Animation::with_durations(frames, durations, mode))
+    }
+
+    // every frame in order, for files without tags
+    pub fn timeline(&self) -> Animation {
+        let frames = (0..self.frames.len()).collect();
+        Animation::with_durations(frames, self.durations.clone(), AnimationMode::Loop)
+    }
+
+    // all the frames side by side, frame `i` is sheet frame `i`
+    pub fn sprite_sheet(&self) -> Result<SpriteSheet, AsepriteError> {
+        let too_big = || invalid("too many frames to fit in one sheet");
+        let width = u32::try_from(self.frames.len())
+            .ok()
+            .and_then(|frames| self.width.checked_mul(frames))
+            .ok_or_else(too_big)?;
+        let len = (width as usize)
+            .checked_mul(self.height as usize)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or_else(too_big)?;
+        let mut pixels = vec![0; len];
+        let row = 4 * self.width as usize;
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            for y in 0..self.height as usize {
+                let start = 4 * y * width as usize + i * row;
+                pixels[start..start + row].copy_from_slice(&frame.pixels[y * row..(y + 1) * row]);
+            }
+        }
+
+        let sprite = Sprite::from_rgba(width, self.height, pixels).unwrap();
+        Ok(SpriteSheet::from_grid(
+            sprite,
+            Grid::new(self.width, self.height),
+        ))
+    }
+}
+
+// everything as it's stored, before flattening
+struct File {
+    width: u32,
+    height: u32,
+    // bits per pixel: 32 rgba, 16 grayscale, 8 indexed
+    depth: u16,
+    transparent: u8,
+    layer_opacity: bool,
+    layers: Vec<Layer>,
+    palette: Vec<[u8; 4]>,
+    // an old palette chunk shouldn't overwrite a new one
+    new_palette: bool,
+    frames: Vec<Frame>,
+    tags: Vec<Tag>,
+}
+
+struct Layer {
+    visible: bool,
+    background: bool,
+    // groups and tilemaps don't have pixels of their own
+    image: bool,
+    // how deep inside groups it is
+    level: u16,
+    opacity: u8,
+    blend: BlendMode,
+}
+
+struct Frame {
+    duration: Duration,
+    cels: Vec<Cel>,
+}
+
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    z: i16,
+    content: Content,
+}
+
+enum Content {
+    Image {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+    // same image as this layer's cel in another frame
+    Linked(usize),
+}
+
+impl File {
+    fn parse(bytes: &[u8]) -> Result<Self, AsepriteError> {
+        let mut header = Reader::new(bytes);
+        header.u32()?; // file size
+        if header.u16()? != 0xA5E0 {
+            return Err(invalid("not an aseprite file"));
+        }
+        let frame_count = header.u16()?;
+        let width = header.u16()? as u32;
+        let height = header.u16()? as u32;
+        let depth = header.u16()?;
+        let flags = header.u32()?;
+        header.take(10)?; // speed and reserved
+        let transparent = header.u8()?;
+
+        if !matches!(depth, 8 | 16 | 32) {
+            return Err(invalid(format!("unsupported color depth {}", depth)));
+        }
+        if width == 0 || height == 0 {
+            return Err(invalid("sprite has no size"));
+        }
+        if width as u64 * height as u64 * frame_count.max(1) as u64 > MAX_PIXELS {
+            return Err(invalid(format!(
+                "{} frames of {}x{} is too big",
+                frame_count, width, height
+            )));
+        }
+
+        let mut file = Self {
+            width,
+            height,
+            depth,
+            transparent,
+            layer_opacity: flags & 1 != 0,
+            layers: Vec::new(),
+            palette: vec![[0, 0, 0, 0]; 256],
+            new_palette: false,
+            frames: Vec::new(),
+            tags: Vec::new(),
+        };
+
+        let mut reader = Reader::new(bytes);
+        reader.take(128)?;
+        for _ in 0..frame_count {
+            let size = reader.u32()? as usize;
+            let mut frame = Reader::new(reader.take(size.saturating_sub(4))?);
+            file.frame(&mut frame)?;
+        }
+
+        Ok(file)
+    }
+
+    fn frame(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        if reader.u16()? != 0xF1FA {
+            return Err(invalid("bad frame header"));
+        }
+        let old_chunks = reader.u16()?;
+        let duration = Duration::from_millis(reader.u16()? as u64);
+        reader.take(2)?;
+        let chunks = match reader.u32()? {
+            0 => old_chunks as u32,
+            chunks => chunks,
+        };
+
+        self.frames.push(Frame {
+            duration,
+            cels: Vec::new(),
+        });
+
+        for _ in 0..chunks {
+            let size = reader.u32()? as usize;
+            let kind = reader.u16()?;
+            let mut chunk = Reader::new(reader.take(size.saturating_sub(6))?);
+            match kind {
+                0x0004 => self.old_palette(&mut chunk)?,
+                0x2004 => self.layer(&mut chunk)?,
+                0x2005 => self.cel(&mut chunk)?,
+                0x2018 => self.tags(&mut chunk)?,
+                0x2019 => self.palette(&mut chunk)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn layer(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        let flags = reader.u16()?;
+        let kind = reader.u16()?;
+        let level = reader.u16()?;
+        reader.take(4)?; // default size
+        let blend = match reader.u16()? {
+            1 => BlendMode::Multiply,
+            2 => BlendMode::Screen,
+            16 => BlendMode::Add,
+            _ => BlendMode::Alpha,
+        };
+        let opacity = reader.u8()?;
+
+        self.layers.push(Layer {
+            visible: flags & 1 != 0,
+            background: flags & 8 != 0,
+            image: kind == 0,
+            level,
+            opacity: if self.layer_opacity { opacity } else { 255 },
+            blend,
+        });
+        Ok(())
+    }
+
+    fn cel(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        let layer = reader.u16()? as usize;
+        let x = reader.i16()? as i32;
+        let y = reader.i16()? as i32;
+        let opacity = reader.u8()?;
+        let kind = reader.u16()?;
+        let z = reader.i16()?;
+        reader.take(5)?;
+
+        let content = match kind {
+            0 | 2 => {
+                let width = reader.u16()? as u32;
+                let height = reader.u16()? as u32;
+                if width as u64 * height as u64 > MAX_PIXELS {
+                    return Err(invalid(format!("{}x{} cel is too big", width, height)));
+                }
+                let len = width as usize * height as usize * (self.depth / 8) as usize;
+                let pixels = if kind == 0 {
+                    reader.take(len)?.to_vec()
+                } else {
+                    decompress_to_vec_zlib_with_limit(reader.rest(), len)
+                        .map_err(|why| invalid(format!("couldn't decompress cel: {}", why)))?
+                };
+                if pixels.len() != len {
+                    return Err(invalid("cel is the wrong size"));
+                }
+                Content::Image {
+                    width,
+                    height,
+                    pixels,
+                }
+            }
+            1 => Content::Linked(reader.u16()? as usize),
+            // tilemap cels
+            _ => return Ok(()),
+        };
+
+        let frame = self.frames.last_mut().unwrap();
+        frame.cels.push(Cel {
+            layer,
+            x,
+            y,
+            opacity,
+            z,
+            content,
+        });
+        Ok(())
+    }
+
+    fn tags(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        let count = reader.u16()?;
+        reader.take(8)?;
+        for _ in 0..count {
+            let from = reader.u16()? as usize;
+            let to = reader.u16()? as usize;
+            let direction = match reader.u8()? {
+                1 => Direction::Reverse,
+                2 => Direction::PingPong,
+                3 => Direction::PingPongReverse,
+                _ => Direction::Forward,
+            };
+            let repeat = reader.u16()?;
+            reader.take(10)?; // reserved and tag color
+            let name = reader.string()?;
+
+            self.tags.push(Tag {
+                name,
+                from,
+                to,
+                direction,
+                repeat,
+            });
+        }
+        Ok(())
+    }
+
+    fn palette(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        reader.take(4)?; // palette size
+        let first = reader.u32()? as usize;
+        let last = reader.u32()? as usize;
+        reader.take(8)?;
+
+        self.new_palette = true;
+        for index in first..=last {
+            let flags = reader.u16()?;
+            let color = [reader.u8()?, reader.u8()?, reader.u8()?, reader.u8()?];
+            if flags & 1 != 0 {
+                reader.string()?;
+            }
+            // only indexed pixels use the palette, and they can't go past 255
+            if let Some(entry) = self.palette.get_mut(index) {
+                *entry = color;
+            }
+        }
+        Ok(())
+    }
+
+    // only used by files older than the new palette chunk
+    fn old_palette(&mut self, reader: &mut Reader) -> Result<(), AsepriteError> {
+        if self.new_palette {
+            return Ok(());
+        }
+
+        let mut index = 0;
+        for _ in 0..reader.u16()? {
+            index += reader.u8()? as usize;
+            let count = match reader.u8()? {
+                0 => 256,
+                count => count as usize,
+            };
+            for _ in 0..count {
+                let color = [reader.u8()?, reader.u8()?, reader.u8()?, 255];
+                if let Some(entry) = self.palette.get_mut(index) {
+                    *entry = color;
+                }
+                index += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn flatten(self) -> Aseprite {
+        let visible = self.visible_layers();
+        let frames = (0..self.frames.len())
+            .map(|index| self.flatten_frame(index, &visible))
+            .collect();
+
+        Aseprite {
+            width: self.width,
+            height: self.height,
+            frames,
+            durations: self.frames.iter().map(|frame| frame.duration).collect(),
+            tags: self.tags,
+        }
+    }
+
+    // hidden groups hide everything inside them too
+    fn visible_layers(&self) -> Vec<bool> {
+        // visibility of each group we're inside, by child level
+        let mut groups: Vec<bool> = Vec::new();
+        self.layers
+            .iter()
+            .map(|layer| {
+                groups.truncate(layer.level as usize);
+                let visible = layer.visible && groups.iter().all(|&group| group);
+                groups.push(visible);
+                visible && layer.image
+            })
+            .collect()
+    }
+
+    fn flatten_frame(&self, index: usize, visible: &[bool]) -> Sprite {
+        let mut canvas = Canvas::new(self.width, self.height);
+        let mut cels: Vec<&Cel> = self.frames[index]
+            .cels
+            .iter()
+            .filter(|cel| visible.get(cel.layer).copied().unwrap_or(false))
+            .collect();
+        // z-index moves a cel up or down relative to the layers
+        cels.sort_by_key(|cel| (cel.layer as i32 + cel.z as i32, cel.z));
+
+        let bytes = (self.depth / 8) as usize;
+        for cel in cels {
+            let Some((width, height, pixels)) = self.image(cel) else {
+                continue;
+            };
+
+            let layer = &self.layers[cel.layer];
+            let opacity = cel.opacity as u32 * layer.opacity as u32 / 255;
+            let mut canvas = canvas.blended(layer.blend);
+            for y in 0..height {
+                for x in 0..width {
+                    let i = bytes * (y as usize * width as usize + x as usize);
+                    let mut pixel = self.color(&pixels[i..i + bytes], layer.background);
+                    pixel[3] = (pixel[3] as u32 * opacity / 255) as u8;
+                    canvas.draw_pixel(cel.x + x as i32, cel.y + y as i32, pixel);
+                }
+            }
+        }
+
+        Sprite::from_image(canvas.to_image())
+    }
+
+    fn image<'a>(&'a self, cel: &'a Cel) -> Option<(u32, u32, &'a [u8])> {
+        match &cel.content {
+            Content::Image {
+                width,
+                height,
+                pixels,
+            } => Some((*width, *height, pixels)),
+            Content::Linked(frame) => {
+                let cel = self
+                    .frames
+                    .get(*frame)?
+                    .cels
+                    .iter()
+                    .find(|other| other.layer == cel.layer)?;
+                match &cel.content {
+                    Content::Image {
+                        width,
+                        height,
+                        pixels,
+                    } => Some((*width, *height, pixels)),
+                    Content::Linked(_) => None,
+                }
+            }
+        }
+    }
+
+    fn color(&self, pixel: &[u8], background: bool) -> [u8; 4] {
+        match pixel {
+            [r, g, b, a] => [*r, *g, *b, *a],
+            [value, alpha] => [*value, *value, *value, *alpha],
+            [index] if *index == self.transparent && !background => [0, 0, 0, 0],
+            [index] => self
+                .palette
+                .get(*index as usize)
+                .copied()
+                .unwrap_or([0, 0, 0, 0]),
+            _ => unreachable!(),
+        }
+    }
+}
+
+// little endian, like everything in the format
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AsepriteError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        bytes
+    }
+
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, AsepriteError> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+}
+
+fn invalid(message: impl Into<String>) -> AsepriteError {
+    AsepriteError::Invalid(message.into())
+}
+
+impl fmt::Display for AsepriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "couldn't read aseprite file: {}", why),
+            Self::Invalid(why) => write!(f, "invalid aseprite file: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteError {}
+
+impl From<io::Error> for AsepriteError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    // writes just enough of the format to test the loader with
+    #[derive(Default)]
+    struct Bytes(Vec<u8>);
+
+    impl Bytes {
+        fn u8(mut self, value: u8) -> Self {
+            self.0.push(value);
+            self
+        }
+
+        fn u16(mut self, value: u16) -> Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn u32(mut self, value: u32) -> Self {
+            self.0.extend(value.to_le_bytes());
+            self
+        }
+
+        fn raw(mut self, bytes: &[u8]) -> Self {
+            self.0.extend(bytes);
+            self
+        }
+
+        fn string(self, value: &str) -> Self {
+            self.u16(value.len() as u16).raw(value.as_bytes())
+        }
+    }
+
+    fn file(width: u16, height: u16, depth: u16, frames: &[Vec<u8>]) -> Vec<u8> {
+        let body = frames.concat();
+        let header = Bytes::default()
+            .u32(128 + body.len() as u32)
+            .u16(0xA5E0)
+            .u16(frames.len() as u16)
+            .u16(width)
+            .u16(height)
+            .u16(depth)
+            .u32(1)
+            .raw(&[0; 10])
+            .u8(0);
+        let mut bytes = header.0;
+        bytes.resize(128, 0);
+        [bytes, body].concat()
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        Bytes::default()
+            .u32(16 + body.len() as u32)
+            .u16(0xF1FA)
+            .u16(chunks.len() as u16)
+            .u16(duration)
+            .u16(0)
+            .u32(chunks.len() as u32)
+            .raw(&body)
+            .0
+    }
+
+    fn chunk(kind: u16, data: Bytes) -> Vec<u8> {
+        Bytes::default()
+            .u32(data.0.len() as u32 + 6)
+            .u16(kind)
+            .raw(&data.0)
+            .0
+    }
+
+    fn layer(visible: bool, name: &str) -> Vec<u8> {
+        let data = Bytes::default()
+            .u16(visible as u16)
+            .u16(0)
+            .u16(0)
+            .u32(0)
+            .u16(0)
+            .u8(255)
+            .raw(&[0; 3])
+            .string(name);
+        chunk(0x2004, data)
+    }
+
+    fn cel(layer: u16, x: u16, y: u16, kind: u16) -> Bytes {
+        Bytes::default()
+            .u16(layer)
+            .u16(x)
+            .u16(y)
+            .u8(255)
+            .u16(kind)
+            .u16(0)
+            .raw(&[0; 5])
+    }
+
+    // two frames over a bottom layer, a hidden layer and a top layer
+    fn aseprite() -> Vec<u8> {
+        let tags = Bytes::default()
+            .u16(1)
+            .raw(&[0; 8])
+            .u16(0)
+            .u16(1)
+            .u8(2)
+            .u16(0)
+            .raw(&[0; 10])
+            .string("walk");
+
+        let first = frame(
+            100,
+            &[
+                layer(true, "bottom"),
+                layer(false, "hidden"),
+                layer(true, "top"),
+                chunk(0x2018, tags),
+                chunk(0x2005, cel(0, 0, 0, 0).u16(2).u16(2).raw(&RED.repeat(4))),
+                chunk(0x2005, cel(1, 0, 0, 0).u16(1).u16(1).raw(&[0, 255, 0, 255])),
+                chunk(
+                    0x2005,
+                    cel(2, 1, 0, 2)
+                        .u16(1)
+                        .u16(1)
+                        .raw(&compress_to_vec_zlib(&BLUE, 6)),
+                ),
+            ],
+        );
+        let second = frame(200, &[chunk(0x2005, cel(0, 0, 0, 1).u16(0))]);
+
+        file(2, 2, 32, &[first, second])
+    }
+
+    #[test]
+    fn aseprite_load() {
+        let aseprite = Aseprite::from_bytes(&aseprite()).unwrap();
+        assert_eq!((aseprite.width, aseprite.height), (2, 2));
+        assert_eq!(
+            aseprite.durations,
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+
+        let [first, second] = &aseprite.frames[..] else {
+            panic!("expected two frames");
+        };
+        assert_eq!(first.pixel(0, 0), RED);
+        assert_eq!(first.pixel(1, 0), BLUE);
+        assert_eq!(second.pixel(1, 0), RED);
+
+        let walk = aseprite.animation("walk").unwrap();
+        assert_eq!(walk.frames, [0, 1]);
+        assert_eq!(walk.mode, AnimationMode::PingPong);
+        assert!(aseprite.animation("run").is_none());
+
+        let sheet = aseprite.sprite_sheet().unwrap();
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.sprite.pixel(3, 0), RED);
+
+        // there, back and there again
+        let mut aseprite = aseprite;
+        aseprite.tags[0].repeat = 3;
+        let walk = aseprite.animation("walk").unwrap();
+        assert_eq!(walk.frames, [0, 1, 0, 1]);
+        assert_eq!(walk.mode, AnimationMode::Once);
+
+        aseprite.width = u32::MAX;
+        assert!(matches!(
+            aseprite.sprite_sheet(),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn aseprite_indexed() {
+        let palette = Bytes::default()
+            .u32(2)
+            .u32(0)
+            .u32(1)
+            .raw(&[0; 8])
+            .u16(0)
+            .raw(&[0, 0, 0, 255])
+            .u16(0)
+            .raw(&BLUE);
+        let pixels = cel(0, 0, 0, 0).u16(2).u16(1).raw(&[0, 1]);
+        let bytes = file(
+            2,
+            1,
+            8,
+            &[frame(
+                100,
+                &[
+                    layer(true, "layer"),
+                    chunk(0x2019, palette),
+                    chunk(0x2005, pixels),
+                ],
+            )],
+        );
+
+        let aseprite = Aseprite::from_bytes(&bytes).unwrap();
+        assert_eq!(aseprite.frames[0].pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(aseprite.frames[0].pixel(1, 0), BLUE);
+    }
+
+    #[test]
+    fn aseprite_errors() {
+        assert!(matches!(
+            Aseprite::from_path("./assets/nothing_here.aseprite"),
+            Err(AsepriteError::Io(_))
+        ));
+        assert!(matches!(
+            Aseprite::from_bytes(&[0; 128]),
+            Err(AsepriteError::Invalid(_))
+        ));
+
+        // a header promising more pixels than any real file has
+        let huge = file(u16::MAX, u16::MAX, 32, &[]);
+        assert!(matches!(
+            Aseprite::from_bytes(&huge),
+            Err(AsepriteError::Invalid(_))
+        ));
+
+        let mut truncated = aseprite();
+        truncated.truncate(150);
+        assert!(matches!(
+            Aseprite::from_bytes(&truncated),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+// how a drawn pixel combines with what's already on the canvas
+// colors are straight (not premultiplied) rgba, like everywhere else
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    // source-over, regular alpha compositing
+    #[default]
+    Alpha,
+    Add,
+    Multiply,
+    Screen,
+    // copy the pixel as-is, alpha and all
+    Replace,
+}
+
+impl BlendMode {
+    pub fn blend(&self, source: [u8; 4], destination: [u8; 4]) -> [u8; 4] {
+        if let BlendMode::Replace = self {
+            return source;
+        }
+
+        match source[3] {
+            0 => return destination,
+            255 if *self == BlendMode::Alpha => return source,
+            _ => {}
+        }
+
+        let source_alpha = source[3] as f32 / 255.0;
+        let destination_alpha = destination[3] as f32 / 255.0;
+        let alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+
+        let mut pixel = [0, 0, 0, (alpha * 255.0).round() as u8];
+        for i in 0..3 {
+            let src = source[i] as f32 / 255.0;
+            let dst = destination[i] as f32 / 255.0;
+
+            // blend where the destination is opaque, plain source where it isn't
+            let mixed = (1.0 - destination_alpha) * src + destination_alpha * self.mix(src, dst);
+            let color = (source_alpha * mixed + destination_alpha * dst * (1.0 - source_alpha)) / alpha;
+            pixel[i] = (color * 255.0).round() as u8;
+        }
+
+        pixel
+    }
+
+    fn mix(&self, source: f32, destination: f32) -> f32 {
+        match self {
+            BlendMode::Alpha | BlendMode::Replace => source,
+            BlendMode::Add => (source + destination).min(1.0),
+            BlendMode::Multiply => source * destination,
+            BlendMode::Screen => source + destination - source * destination,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY: [u8; 4] = [100, 100, 100, 255];
+
+    #[test]
+    fn blend_alpha() {
+        let mode = BlendMode::Alpha;
+        assert_eq!(mode.blend([200, 0, 0, 255], GRAY), [200, 0, 0, 255]);
+        assert_eq!(mode.blend([200, 0, 0, 0], GRAY), GRAY);
+        assert_eq!(mode.blend([200, 0, 0, 128], GRAY), [150, 50, 50, 255]);
+        assert_eq!(mode.blend([200, 0, 0, 128], [0, 0, 0, 0]), [200, 0, 0, 128]);
+    }
+
+    #[test]
+    fn blend_modes() {
+        let red = [200, 0, 0, 255];
+        assert_eq!(BlendMode::Add.blend(red, GRAY), [255, 100, 100, 255]);
+        assert_eq!(BlendMode::Multiply.blend(red, GRAY), [78, 0, 0, 255]);
+        assert_eq!(BlendMode::Screen.blend(red, GRAY), [222, 100, 100, 255]);
+        assert_eq!(BlendMode::Replace.blend([1, 2, 3, 0], GRAY), [1, 2, 3, 0]);
+    }
+}
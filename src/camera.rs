@@ -0,0 +1,400 @@
+// draw in world coordinates and let the camera scroll, zoom and turn them onto the canvas
+
+use bevy_ecs::system::{Res, ResMut, Resource};
+
+use crate::{
+    app::{PandaBuilder, Plugin},
+    geometry::{Circle, Polygon, Rectangle, Vec2},
+    sheet::SpriteSheet,
+    sprite::{DrawParams, Sprite},
+    tilemap::Tilemap,
+    Canvas, Duration, Time,
+};
+
+#[derive(Resource, Clone, Debug)]
+pub struct Camera {
+    // the world point in the middle of the canvas
+    pub position: Vec2,
+    // each world pixel is `zoom` canvas pixels wide, whole numbers keep pixels square
+    pub zoom: u32,
+    // radians, the world turns the other way on screen
+    pub rotation: f32,
+    // the view is kept inside this part of the world, ignoring rotation
+    pub bounds: Option<Rectangle>,
+    // how quickly `follow` catches up, 0 snaps straight to the target
+    pub smoothing: f32,
+    // canvas size, kept up to date by `update_camera`
+    size: Vec2,
+    shake: Shake,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Shake {
+    intensity: f32,
+    duration: Duration,
+    remaining: Duration,
+    offset: Vec2,
+    // xorshift state, so shakes play out the same every run
+    seed: u32,
+}
+
+impl Camera {
+    // looks at the middle of a `width` by `height` canvas, so world and canvas line up
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = Vec2::new(width as f32, height as f32);
+        Self {
+            position: size / 2.0,
+            zoom: 1,
+            rotation: 0.0,
+            bounds: None,
+            smoothing: 0.0,
+            size,
+            shake: Shake {
+                seed: 0x9E37_79B9,
+                ..Shake::default()
+            },
+        }
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        self.size = Vec2::new(width as f32, height as f32);
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn scale(&self) -> f32 {
+        self.zoom.max(1) as f32
+    }
+
+    // where the camera really is, shake included
+    fn eye(&self) -> Vec2 {
+        self.position + self.shake.offset
+    }
+
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let turned = Vec2::from_angle(-self.rotation).rotate(point - self.eye());
+        turned * self.scale() + self.size / 2.0
+    }
+
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let turned = (point - self.size / 2.0) / self.scale();
+        Vec2::from_angle(self.rotation).rotate(turned) + self.eye()
+    }
+
+    // the part of the world on screen, grown to fit when rotated
+    pub fn visible_area(&self) -> Rectangle {
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(self.size.x, 0.0),
+            Vec2::new(0.0, self.size.y),
+            self.size,
+        ]
+        .map(|corner| self.screen_to_world(corner));
+
+        let min = corners
+            .iter()
+            .fold(Vec2::splat(f32::INFINITY), |min, corner| min.min(*corner));
+        let max = corners
+            .iter()
+            .fold(Vec2::splat(f32::NEG_INFINITY), |max, corner| {
+                max.max(*corner)
+            });
+        Rectangle {
+            point: min,
+            size: max - min,
+        }
+    }
+
+    // eases towards `target`, call it once a frame
+    pub fn follow(&mut self, target: Vec2, delta: Duration) {
+        if self.smoothing <= 0.0 {
+            self.position = target;
+        } else {
+            // framerate independent exponential smoothing
+            let amount = 1.0 - (-self.smoothing * delta.as_secs_f32()).exp();
+            self.position += (target - self.position) * amount;
+        }
+        self.clamp();
+    }
+
+    // keeps the view inside `bounds`, centering on them when they're smaller than the view
+    pub fn clamp(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        let half = self.size / (2.0 * self.scale());
+        let (left, top, right, bottom) = bounds.bounds();
+        let clamp = |value: f32, min: f32, max: f32, half: f32| {
+            if max - min <= 2.0 * half {
+                (min + max) / 2.0
+            } else {
+                value.clamp(min + half, max - half)
+            }
+        };
+
+        self.position = Vec2::new(
+            clamp(self.position.x, left, right, half.x),
+            clamp(self.position.y, top, bottom, half.y),
+        );
+    }
+
+    // `intensity` is in world pixels and fades out over `duration`
+    pub fn shake(&mut self, intensity: f32, duration: Duration) {
+        self.shake.intensity = intensity;
+        self.shake.duration = duration;
+        self.shake.remaining = duration;
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        !self.shake.remaining.is_zero()
+    }
+
+    // moves the shake along, `update_camera` calls this for the `Camera` resource
+    pub fn update(&mut self, delta: Duration) {
+        let shake = &mut self.shake;
+        shake.remaining = shake.remaining.saturating_sub(delta);
+        if shake.remaining.is_zero() {
+            shake.offset = Vec2::ZERO;
+            return;
+        }
+
+        let strength =
+            shake.intensity * shake.remaining.as_secs_f32() / shake.duration.as_secs_f32();
+        let mut random = || {
+            shake.seed ^= shake.seed << 13;
+            shake.seed ^= shake.seed >> 17;
+            shake.seed ^= shake.seed << 5;
+            shake.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+        shake.offset = Vec2::new(random(), random()) * strength;
+    }
+
+    pub fn view<'a>(&'a self, canvas: &'a mut Canvas) -> View<'a> {
+        View {
+            camera: self,
+            canvas,
+        }
+    }
+}
+
+// adds `update_camera`
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, builder: &mut PandaBuilder) {
+        builder.add_system(update_camera);
+    }
+}
+
+// keeps the camera the same size as the canvas and moves shakes along
+pub fn update_camera(mut camera: ResMut<Camera>, canvas: Res<Canvas>, time: Res<Time>) {
+    camera.size = Vec2::new(canvas.width() as f32, canvas.height() as f32);
+    camera.update(**time);
+}
+
+// the canvas as seen through a camera, everything here takes world coordinates
+pub struct View<'a> {
+    camera: &'a Camera,
+    canvas: &'a mut Canvas,
+}
+
+impl View<'_> {
+    pub fn camera(&self) -> &Camera {
+        self.camera
+    }
+
+    pub fn canvas(&mut self) -> &mut Canvas {
+        self.canvas
+    }
+
+    // fills the whole world pixel `point` is in
+    pub fn draw_pixel(&mut self, point: Vec2, pixel: [u8; 4]) {
+        let point = point.floor();
+        self.draw_rectangle(&Rectangle::new(point.x, point.y, 1.0, 1.0), pixel);
+    }
+
+    // lines stay one canvas pixel thick at any zoom
+    pub fn draw_line(&mut self, start: &Vec2, end: &Vec2, pixel: [u8; 4]) {
+        let start = self.camera.world_to_screen(*start);
+        let end = self.camera.world_to_screen(*end);
+        self.canvas.draw_line(&start, &end, pixel);
+    }
+
+    pub fn draw_rectangle(&mut self, rectangle: &Rectangle, pixel: [u8; 4]) {
+        let (left, top, right, bottom) = rectangle.bounds();
+        self.fill(rectangle, pixel, |point| {
+            point.x >= left && point.x < right && point.y >= top && point.y < bottom
+        });
+    }
+
+    pub fn draw_circle(&mut self, circle: &Circle, pixel: [u8; 4]) {
+        let area = Rectangle {
+            point: circle.center - circle.radius,
+            size: Vec2::splat(2.0 * circle.radius),
+        };
+        self.fill(&area, pixel, |point| {
+            (point - circle.center).length_squared() < circle.radius.powi(2)
+        });
+    }
+
+    pub fn draw_polygon(&mut self, polygon: &Polygon, pixel: [u8; 4]) {
+        let points = polygon
+            .points
+            .iter()
+            .map(|point| self.camera.world_to_screen(*point))
+            .collect();
+        Polygon {
+            points,
+            closed: polygon.closed,
+        }
+        .draw(self.canvas, pixel);
+    }
+
+    pub fn draw_sprite(&mut self, sprite: &Sprite, point: &Vec2, params: &DrawParams) {
+        let (point, params) = self.transform(point, params);
+        sprite.draw_with(self.canvas, &point, &params);
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        sheet: &SpriteSheet,
+        index: usize,
+        point: &Vec2,
+        params: &DrawParams,
+    ) {
+        let (point, params) = self.transform(point, params);
+        sheet.draw_with(self.canvas, index, &point, &params);
+    }
+
+    // only the tiles the camera can see
+    pub fn draw_tilemap(&mut self, tilemap: &Tilemap) {
+        tilemap.visible_tiles(&self.camera.visible_area(), |point, tile| {
+            self.draw_frame(&tilemap.tileset, tile.index, &point, &tile.draw_params());
+        });
+    }
+
+    // sprites keep their pivot on the same world point
+    fn transform(&self, point: &Vec2, params: &DrawParams) -> (Vec2, DrawParams) {
+        let pivot = self.camera.world_to_screen(*point + params.pivot);
+        let params = DrawParams {
+            rotation: params.rotation - self.camera.rotation,
+            scale: params.scale * self.camera.scale(),
+            ..*params
+        };
+        (pivot - params.pivot, params)
+    }
+
+    // draws every canvas pixel whose center lands `inside` the shape, `area` bounds it in the world
+    fn fill(&mut self, area: &Rectangle, pixel: [u8; 4], inside: impl Fn(Vec2) -> bool) {
+        let (left, top, right, bottom) = area.bounds();
+        let corners = [
+            Vec2::new(left, top),
+            Vec2::new(right, top),
+            Vec2::new(left, bottom),
+            Vec2::new(right, bottom),
+        ]
+        .map(|corner| self.camera.world_to_screen(corner));
+
+        let canvas_size = Vec2::new(self.canvas.width() as f32, self.canvas.height() as f32);
+        let min = corners
+            .iter()
+            .fold(Vec2::splat(f32::INFINITY), |min, corner| min.min(*corner));
+        let max = corners
+            .iter()
+            .fold(Vec2::splat(f32::NEG_INFINITY), |max, corner| {
+                max.max(*corner)
+            });
+        let min = min.floor().max(Vec2::ZERO).as_ivec2();
+        let max = max.ceil().min(canvas_size).as_ivec2();
+
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if inside(self.camera.screen_to_world(center)) {
+                    self.canvas.draw_pixel(x, y, pixel);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+
+    #[test]
+    fn camera_transform() {
+        let mut camera = Camera::new(8, 6);
+        assert_eq!(
+            camera.world_to_screen(Vec2::new(1.0, 2.0)),
+            Vec2::new(1.0, 2.0)
+        );
+
+        camera.position = Vec2::new(10.0, 10.0);
+        camera.zoom = 2;
+        assert_eq!(
+            camera.world_to_screen(Vec2::new(11.0, 10.0)),
+            Vec2::new(6.0, 3.0)
+        );
+
+        camera.rotation = 0.7;
+        let point = Vec2::new(-3.0, 4.5);
+        let back = camera.screen_to_world(camera.world_to_screen(point));
+        assert!((back - point).length() < 1e-4);
+    }
+
+    #[test]
+    fn camera_follow_and_clamp() {
+        let mut camera = Camera::new(10, 10);
+        camera.bounds = Some(Rectangle::new(0.0, 0.0, 40.0, 8.0));
+        camera.follow(Vec2::new(100.0, 0.0), Duration::from_millis(16));
+        // too short to scroll up and down, so it sits in the middle
+        assert_eq!(camera.position, Vec2::new(35.0, 4.0));
+
+        camera.smoothing = 10.0;
+        camera.follow(Vec2::new(5.0, 4.0), Duration::from_millis(100));
+        assert!(camera.position.x > 5.0 && camera.position.x < 35.0);
+    }
+
+    #[test]
+    fn camera_shake() {
+        let mut camera = Camera::new(10, 10);
+        camera.shake(2.0, Duration::from_millis(100));
+        camera.update(Duration::from_millis(50));
+        assert!(camera.is_shaking());
+        assert!(camera.shake.offset.length() <= 2.0 * std::f32::consts::SQRT_2);
+
+        camera.update(Duration::from_millis(50));
+        assert!(!camera.is_shaking());
+        assert_eq!(camera.world_to_screen(Vec2::ZERO), Vec2::ZERO);
+    }
+
+    #[test]
+    fn camera_view() {
+        let mut canvas = Canvas::new(6, 6);
+        let mut camera = Camera::new(6, 6);
+        camera.zoom = 2;
+        camera.position = Vec2::new(1.5, 1.5);
+
+        // a world pixel is a 2x2 block, and the camera is looking at the middle of this one
+        camera
+            .view(&mut canvas)
+            .draw_rectangle(&Rectangle::new(1.0, 1.0, 1.0, 1.0), RED);
+        assert_eq!(canvas.get_pixel(2, 2), RED);
+        assert_eq!(canvas.get_pixel(3, 3), RED);
+        assert_eq!(canvas.get_pixel(4, 4), [0, 0, 0, 0]);
+
+        let sprite = Sprite::from_rgba(1, 1, RED.to_vec()).unwrap();
+        let mut canvas = Canvas::new(6, 6);
+        camera
+            .view(&mut canvas)
+            .draw_sprite(&sprite, &Vec2::new(2.0, 1.0), &DrawParams::default());
+        assert_eq!(canvas.get_pixel(5, 3), RED);
+        assert_eq!(canvas.get_pixel(3, 3), [0, 0, 0, 0]);
+    }
+}
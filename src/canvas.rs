@@ -1,25 +1,112 @@
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
+
 use bevy_ecs::system::Resource;
-use pixels::{wgpu::Color as WGPUColor, Pixels};
+use image::{ImageFormat, ImageResult, RgbaImage};
+use pixels::wgpu::Color as WGPUColor;
 
 use crate::{
+    blend::BlendMode,
     geometry::Vec2,
     line::Bresenham,
 };
 
+// plain rgba buffer, doesn't need a window to draw to!
+// `Panda` copies it into the window's frame when presenting
 #[derive(Resource)]
-pub struct Canvas(pub Pixels);
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    blend: BlendMode,
+}
 
 impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; 4 * (width * height) as usize],
+            blend: BlendMode::default(),
+        }
+    }
+
+    // a blank canvas of the new size, drawing starts over
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; 4 * (width * height) as usize];
+    }
+
     pub fn width(&self) -> u32 {
-        self.0.texture().width()
+        self.width
     }
 
     pub fn height(&self) -> u32 {
-        self.0.texture().height()
+        self.height
     }
 
     pub fn pixels(&mut self) -> &mut [u8] {
-        self.0.frame_mut()
+        &mut self.pixels
+    }
+
+    pub fn frame(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+            .expect("canvas buffer should match its size")
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        self.to_image().save_with_format(path, ImageFormat::Png)
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend
+    }
+
+    // used by every draw call from now on
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend = mode;
+    }
+
+    // use `mode` for a single call, eg. `canvas.blended(BlendMode::Add).draw_circle(..)`
+    pub fn blended(&mut self, mode: BlendMode) -> Blended<'_> {
+        let previous = self.blend;
+        self.blend = mode;
+        Blended {
+            canvas: self,
+            previous,
+        }
+    }
+}
+
+// puts the old blend mode back when dropped
+pub struct Blended<'a> {
+    canvas: &'a mut Canvas,
+    previous: BlendMode,
+}
+
+impl Drop for Blended<'_> {
+    fn drop(&mut self) {
+        self.canvas.blend = self.previous;
+    }
+}
+
+impl Deref for Blended<'_> {
+    type Target = Canvas;
+    fn deref(&self) -> &Self::Target {
+        self.canvas
+    }
+}
+
+impl DerefMut for Blended<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.canvas
     }
 }
 
@@ -32,7 +119,7 @@ impl Canvas {
 
     pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
         let index = 4 * (y * self.width() + x) as usize;
-        let slice = &self.0.frame()[index..index + 4];
+        let slice = &self.frame()[index..index + 4];
         slice.try_into().unwrap()
     }
 
@@ -42,7 +129,9 @@ impl Canvas {
         };
 
         let index = (4 * (y * self.width() as i32 + x)) as usize;
-        self.pixels()[index..(index + 4)].copy_from_slice(&pixel[..4]);
+        let slice = &mut self.pixels[index..(index + 4)];
+        let blended = self.blend.blend(pixel, slice.try_into().unwrap());
+        slice.copy_from_slice(&blended);
     }
 
     pub fn draw_nearest_pixel(&mut self, x: f32, y: f32, pixel: [u8; 4]) {
@@ -79,6 +168,7 @@ impl Canvas {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -121,6 +211,17 @@ impl From<WGPUColor> for Color {
     }
 }
 
+impl From<Color> for WGPUColor {
+    fn from(value: Color) -> Self {
+        WGPUColor {
+            r: value.r as f64 / 255.0,
+            g: value.g as f64 / 255.0,
+            b: value.b as f64 / 255.0,
+            a: value.a as f64 / 255.0,
+        }
+    }
+}
+
 impl From<u32> for Color {
     fn from(value: u32) -> Self {
         Color {
@@ -132,3 +233,53 @@ impl From<u32> for Color {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canvas_new() {
+        let canvas = Canvas::new(4, 3);
+        assert_eq!(canvas.width(), 4);
+        assert_eq!(canvas.height(), 3);
+        assert_eq!(canvas.frame().len(), 4 * 4 * 3);
+        assert_eq!(canvas.get_pixel(3, 2), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn canvas_draw_pixel() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.clear(Color::BLACK.pixel());
+        canvas.draw_pixel(1, 2, Color::WHITE.pixel());
+        canvas.draw_pixel(-1, 0, Color::WHITE.pixel());
+        canvas.draw_pixel(4, 0, Color::WHITE.pixel());
+
+        assert_eq!(canvas.get_pixel(1, 2), Color::WHITE.pixel());
+        assert_eq!(canvas.get_pixel(0, 0), Color::BLACK.pixel());
+        assert_eq!(canvas.get_pixel(3, 0), Color::BLACK.pixel());
+    }
+
+    #[test]
+    fn canvas_draw_rectangle() {
+        let mut canvas = Canvas::new(5, 5);
+        canvas.draw_rectangle(3, 3, 4, 4, Color::WHITE.pixel());
+
+        let filled = canvas.frame().chunks(4).filter(|pixel| pixel[3] == 255).count();
+        assert_eq!(filled, 4);
+        assert_eq!(canvas.get_pixel(4, 4), Color::WHITE.pixel());
+    }
+
+    #[test]
+    fn canvas_blended() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.clear([100, 100, 100, 255]);
+
+        canvas.blended(BlendMode::Replace).draw_pixel(0, 0, [0, 0, 0, 0]);
+        canvas.draw_pixel(1, 0, [0, 0, 0, 0]);
+
+        assert_eq!(canvas.blend_mode(), BlendMode::Alpha);
+        assert_eq!(canvas.get_pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(canvas.get_pixel(1, 0), [100, 100, 100, 255]);
+    }
+}
@@ -0,0 +1,160 @@
+// what can go wrong opening and drawing to the window, and a hook to decide what happens next
+
+use std::fmt;
+
+use bevy_ecs::{system::Resource, world::World};
+use pixels::TextureError;
+use winit::error::OsError;
+
+use crate::{input::RecordingError, PandaOptions};
+
+#[derive(Debug)]
+pub enum PandaError {
+    // the name of the option that was zero
+    InvalidOptions(&'static str),
+    Window(OsError),
+    // no adapter, device or surface that can draw to the window
+    Surface(pixels::Error),
+    Resize(TextureError),
+    Render(pixels::Error),
+    // saving a `Recorder`'s file
+    Recording(RecordingError),
+}
+
+// what to do after an error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    Exit,
+    // carry on, skipping whatever failed
+    Continue,
+    // make the surface again on a software adapter, slower but works almost anywhere
+    Fallback,
+}
+
+// without one, errors get printed and the panda exits
+#[derive(Resource)]
+pub struct ErrorHook(Box<dyn FnMut(&PandaError) -> OnError + Send + Sync>);
+
+impl ErrorHook {
+    pub fn new(hook: impl FnMut(&PandaError) -> OnError + Send + Sync + 'static) -> Self {
+        Self(Box::new(hook))
+    }
+}
+
+pub(crate) fn handle_error(world: &mut World, error: &PandaError) -> OnError {
+    match world.get_resource_mut::<ErrorHook>() {
+        Some(mut hook) => (hook.0)(error),
+        None => {
+            eprintln!("{}", error);
+            OnError::Exit
+        }
+    }
+}
+
+pub(crate) fn check_options(options: &PandaOptions) -> Result<(), PandaError> {
+    let zero = [
+        ("width", options.width),
+        ("height", options.height),
+        ("scale", options.scale),
+    ]
+    .into_iter()
+    .find(|(_, value)| *value == 0);
+    match zero {
+        Some((name, _)) => Err(PandaError::InvalidOptions(name)),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for PandaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOptions(name) => write!(f, "{} can't be zero", name),
+            Self::Window(why) => write!(f, "couldn't open a window: {}", why),
+            Self::Surface(why) => write!(f, "couldn't draw to the window: {}", why),
+            Self::Resize(why) => write!(f, "couldn't resize the window: {}", why),
+            Self::Render(why) => write!(f, "couldn't render: {}", why),
+            Self::Recording(why) => write!(f, "{}", why),
+        }
+    }
+}
+
+impl std::error::Error for PandaError {}
+
+impl From<OsError> for PandaError {
+    fn from(value: OsError) -> Self {
+        Self::Window(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Panda;
+
+    #[test]
+    fn invalid_options() {
+        for (options, name) in [
+            (
+                PandaOptions {
+                    width: 0,
+                    ..PandaOptions::default()
+                },
+                "width",
+            ),
+            (
+                PandaOptions {
+                    scale: 0,
+                    ..PandaOptions::default()
+                },
+                "scale",
+            ),
+        ] {
+            match Panda::try_new(options) {
+                Err(PandaError::InvalidOptions(zero)) => assert_eq!(zero, name),
+                _ => panic!("{} of zero should be invalid", name),
+            }
+        }
+        // headless pandas check the same options
+        let options = PandaOptions {
+            height: 0,
+            ..PandaOptions::default()
+        };
+        assert!(matches!(
+            Panda::builder(options).try_build_headless(),
+            Err(PandaError::InvalidOptions("height"))
+        ));
+        assert!(check_options(&PandaOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn exit_without_hook() {
+        let mut world = World::new();
+        for error in [
+            PandaError::Resize(TextureError::TextureHeight(0)),
+            PandaError::Surface(pixels::Error::AdapterNotFound),
+        ] {
+            assert_eq!(handle_error(&mut world, &error), OnError::Exit);
+        }
+    }
+
+    #[test]
+    fn error_hook() {
+        let mut world = World::new();
+        let error = PandaError::Resize(TextureError::TextureWidth(0));
+
+        let mut errors = 0;
+        world.insert_resource(ErrorHook::new(move |error| {
+            errors += 1;
+            match error {
+                PandaError::Resize(_) if errors < 2 => OnError::Continue,
+                _ => OnError::Fallback,
+            }
+        }));
+        assert_eq!(handle_error(&mut world, &error), OnError::Continue);
+        assert_eq!(handle_error(&mut world, &error), OnError::Fallback);
+        assert_eq!(
+            error.to_string(),
+            "couldn't resize the window: Texture width is invalid: 0"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// bevy events that last two frames, plus the ones the window sends
+
+use std::path::PathBuf;
+
+use bevy_ecs::{
+    event::{Event, Events},
+    system::Resource,
+    world::World,
+};
+use winit::event::WindowEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFocused(pub bool);
+
+// with a window, `Stage::Teardown` runs as soon as this is sent, so teardown systems
+// can read it, then the event loop exits and no more frames run.
+// headless pandas only stop at `Panda::teardown`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CloseRequested;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDropped(pub PathBuf);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CharTyped(pub char);
+
+// how to update each kind of event, once a frame
+#[derive(Resource, Default)]
+struct EventUpdates(Vec<fn(&mut World)>);
+
+pub(crate) fn add_event<E: Event>(world: &mut World) {
+    if world.contains_resource::<Events<E>>() {
+        return;
+    }
+    world.init_resource::<Events<E>>();
+    world
+        .get_resource_or_insert_with(EventUpdates::default)
+        .0
+        .push(|world| world.resource_mut::<Events<E>>().update());
+}
+
+pub(crate) fn add_window_events(world: &mut World) {
+    add_event::<WindowResized>(world);
+    add_event::<WindowFocused>(world);
+    add_event::<CloseRequested>(world);
+    add_event::<FileDropped>(world);
+    add_event::<CharTyped>(world);
+}
+
+// events sent last frame are dropped, this frame's can still be read next frame
+pub(crate) fn update_events(world: &mut World) {
+    let Some(updates) = world.get_resource::<EventUpdates>() else {
+        return;
+    };
+    for update in updates.0.clone() {
+        update(world);
+    }
+}
+
+pub(crate) fn send_window_event(world: &mut World, event: &WindowEvent) {
+    match event {
+        WindowEvent::Resized(size) => world.send_event(WindowResized {
+            width: size.width,
+            height: size.height,
+        }),
+        WindowEvent::Focused(focused) => world.send_event(WindowFocused(*focused)),
+        WindowEvent::CloseRequested => world.send_event(CloseRequested),
+        WindowEvent::DroppedFile(path) => world.send_event(FileDropped(path.clone())),
+        WindowEvent::ReceivedCharacter(char) => world.send_event(CharTyped(*char)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        event::{EventReader, EventWriter},
+        system::{Local, ResMut, Resource},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::{Duration, Panda, PandaOptions};
+
+    struct Hit(u32);
+
+    #[derive(Resource, Default)]
+    struct Seen(Vec<u32>);
+
+    fn send_hits(mut hits: EventWriter<Hit>, mut sent: Local<u32>) {
+        // only on the first frame
+        if *sent == 0 {
+            hits.send(Hit(7));
+        }
+        *sent += 1;
+    }
+
+    fn read_hits(mut hits: EventReader<Hit>, mut seen: ResMut<Seen>) {
+        seen.0.extend(hits.iter().map(|hit| hit.0));
+    }
+
+    #[test]
+    fn events_clear_after_two_frames() {
+        let mut panda = Panda::builder(PandaOptions::default())
+            .add_event::<Hit>()
+            .init_resource::<Seen>()
+            .add_system(send_hits)
+            .add_render_system(read_hits)
+            .build_headless();
+
+        panda.update_frames(3, Duration::from_millis(16));
+        assert_eq!(panda.world.resource::<Seen>().0, [7]);
+        assert!(panda.world.resource::<Events<Hit>>().is_empty());
+    }
+
+    #[test]
+    fn window_events() {
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.send_event(WindowEvent::Resized(PhysicalSize::new(64, 48)));
+        panda.send_event(WindowEvent::Focused(false));
+        panda.type_char('p');
+        panda.type_char('!');
+        panda.update(Duration::from_millis(16));
+
+        fn read<E: Event + Clone>(panda: &Panda) -> Vec<E> {
+            let events = panda.world.resource::<Events<E>>();
+            events.get_reader().iter(events).cloned().collect()
+        }
+        assert_eq!(
+            read::<WindowResized>(&panda),
+            [WindowResized {
+                width: 64,
+                height: 48
+            }]
+        );
+        assert_eq!(read::<WindowFocused>(&panda), [WindowFocused(false)]);
+        assert_eq!(read::<CharTyped>(&panda), [CharTyped('p'), CharTyped('!')]);
+        assert!(read::<CloseRequested>(&panda).is_empty());
+    }
+}
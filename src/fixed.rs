@@ -0,0 +1,156 @@
+// `Stage::FixedUpdate` runs at a steady rate no matter the frame rate,
+// so physics behaves the same on every machine
+
+use bevy_ecs::system::Resource;
+
+use crate::Duration;
+
+#[derive(Resource, Clone, Debug)]
+pub struct FixedTime {
+    step: Duration,
+    // the most fixed updates in one frame, after a long frame the rest is dropped
+    // instead of slowing every frame after it down too
+    pub max_steps: u32,
+    accumulated: Duration,
+    steps: u32,
+}
+
+impl FixedTime {
+    pub fn new(step: Duration) -> Self {
+        let mut fixed = Self {
+            step: Duration::ZERO,
+            max_steps: 5,
+            accumulated: Duration::ZERO,
+            steps: 0,
+        };
+        fixed.set_step(step);
+        fixed
+    }
+
+    // 0 hz and less, or nan, never step
+    pub fn from_hz(hz: f64) -> Self {
+        let step = match hz > 0.0 {
+            true => Duration::try_from_secs_f64(1.0 / hz).unwrap_or(Duration::MAX),
+            false => Duration::MAX,
+        };
+        Self::new(step)
+    }
+
+    // time between fixed updates, use this instead of `Time` in fixed systems
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    // at least a nanosecond
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step.max(Duration::from_nanos(1));
+    }
+
+    // how far into the next fixed update we are, from 0 to 1,
+    // for drawing things between where they were and where they are
+    pub fn alpha(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    // how many fixed updates ran this frame
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    pub(crate) fn tick(&mut self, delta: Duration) -> u32 {
+        self.accumulated = self.accumulated.saturating_add(delta);
+        self.steps = 0;
+        while self.accumulated >= self.step && self.steps < self.max_steps {
+            self.accumulated -= self.step;
+            self.steps += 1;
+        }
+
+        // too far behind to catch up
+        if self.accumulated >= self.step {
+            let nanos = self.accumulated.as_nanos() % self.step.as_nanos();
+            self.accumulated = Duration::from_nanos(nanos as u64);
+        }
+        self.steps
+    }
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::from_hz(60.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::Schedule,
+        system::{ResMut, Resource},
+    };
+
+    use super::*;
+    use crate::{Panda, PandaOptions};
+
+    #[test]
+    fn fixed_time_steps() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10));
+        assert_eq!(fixed.tick(Duration::from_millis(4)), 0);
+        assert!((fixed.alpha() - 0.4).abs() < 1e-6);
+        assert_eq!(fixed.tick(Duration::from_millis(7)), 1);
+        assert_eq!(fixed.tick(Duration::from_millis(25)), 2);
+        assert!((fixed.alpha() - 0.6).abs() < 1e-6);
+        assert_eq!(fixed.steps(), 2);
+    }
+
+    #[test]
+    fn fixed_time_catch_up() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10));
+        fixed.max_steps = 3;
+        assert_eq!(fixed.tick(Duration::from_millis(1005)), 3);
+        // the rest of the second is gone, apart from the part step
+        assert!((fixed.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(fixed.tick(Duration::from_millis(5)), 1);
+    }
+
+    #[test]
+    fn fixed_time_odd_steps() {
+        for hz in [0.0, -30.0, f64::NAN, 1e-300] {
+            let mut fixed = FixedTime::from_hz(hz);
+            assert_eq!(fixed.step(), Duration::MAX);
+            assert_eq!(fixed.tick(Duration::from_secs(60)), 0);
+        }
+
+        let mut fixed = FixedTime::from_hz(f64::INFINITY);
+        assert_eq!(fixed.step(), Duration::from_nanos(1));
+        fixed.set_step(Duration::ZERO);
+        assert_eq!(fixed.step(), Duration::from_nanos(1));
+        assert_eq!(fixed.tick(Duration::from_millis(10)), 5);
+    }
+
+    #[derive(Resource, Default)]
+    struct Ticks(u32);
+
+    fn tick(mut ticks: ResMut<Ticks>) {
+        ticks.0 += 1;
+    }
+
+    #[test]
+    fn fixed_schedule() {
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.insert_resource(FixedTime::from_hz(30.0));
+        panda.world.init_resource::<Ticks>();
+
+        let mut fixed = Schedule::new();
+        fixed.add_system(tick);
+
+        // a second at 60 and at 144 frames per second
+        for (frames, fps) in [(60, 60.0), (144, 144.0)] {
+            panda.world.resource_mut::<Ticks>().0 = 0;
+            for _ in 0..frames {
+                let delta = Duration::from_secs_f64(1.0 / fps);
+                panda.step_fixed(&mut Schedule::new(), &mut fixed, delta);
+            }
+            let ticks = panda.world.resource::<Ticks>().0;
+            assert!((29..=31).contains(&ticks), "{} ticks at {} fps", ticks, fps);
+        }
+    }
+}
@@ -1,7 +1,10 @@
 pub use glam::Vec2;
 
+use bevy_ecs::prelude::Component;
+
 use crate::Canvas;
 
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
     pub point: Vec2,
     pub size: Vec2,
@@ -38,6 +41,7 @@ impl Rectangle {
     }
 }
 
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
 pub struct Circle {
     pub center: Vec2,
     pub radius: f32,
@@ -62,6 +66,31 @@ impl Circle {
     }
 }
 
+// open polygons are polylines
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            closed: true,
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, pixel: [u8; 4]) {
+        for pair in self.points.windows(2) {
+            canvas.draw_line(&pair[0], &pair[1], pixel);
+        }
+        if let (true, [first, .., last]) = (self.closed, self.points.as_slice()) {
+            canvas.draw_line(last, first, pixel);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,191 @@
+// compare rendered frames against reference pngs
+// run tests with `PANDA_BLESS=1` to (re)write the reference images
+
+use std::{
+    env,
+    ffi::OsStr,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::{ImageError, Rgba, RgbaImage};
+
+use crate::Canvas;
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Missing(PathBuf),
+    Io(io::Error),
+    Image(ImageError),
+    Size {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    // how many pixels differ, and where the diff image went
+    Mismatch { pixels: usize, diff: PathBuf },
+}
+
+// `tolerance` is the largest difference allowed per channel
+pub fn compare_golden(
+    canvas: &Canvas,
+    path: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<(), GoldenError> {
+    let path = path.as_ref();
+    let actual = canvas.to_image();
+
+    if blessing(env::var_os("PANDA_BLESS").as_deref()) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        actual.save(path)?;
+        return Ok(());
+    }
+
+    if !path.exists() {
+        return Err(GoldenError::Missing(path.to_path_buf()));
+    }
+
+    let expected = image::open(path)?.to_rgba8();
+    if expected.dimensions() != actual.dimensions() {
+        return Err(GoldenError::Size {
+            expected: expected.dimensions(),
+            found: actual.dimensions(),
+        });
+    }
+
+    let (diff, pixels) = diff_images(&expected, &actual, tolerance);
+    if pixels == 0 {
+        return Ok(());
+    }
+
+    let diff_path = path.with_extension("diff.png");
+    diff.save(&diff_path)?;
+    actual.save(path.with_extension("actual.png"))?;
+
+    Err(GoldenError::Mismatch {
+        pixels,
+        diff: diff_path,
+    })
+}
+
+pub fn assert_golden(canvas: &Canvas, path: impl AsRef<Path>, tolerance: u8) {
+    if let Err(why) = compare_golden(canvas, path, tolerance) {
+        panic!("{}", why);
+    }
+}
+
+// mismatched pixels in red over a faded copy of the expected image
+fn diff_images(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut pixels = 0;
+
+    for (x, y, expected) in expected.enumerate_pixels() {
+        let actual = actual.get_pixel(x, y);
+        let matches = expected
+            .0
+            .iter()
+            .zip(actual.0)
+            .all(|(&a, b)| a.abs_diff(b) <= tolerance);
+
+        let pixel = if matches {
+            let [r, g, b, _] = expected.0;
+            let faded = ((r as u32 + g as u32 + b as u32) / 12) as u8;
+            Rgba([faded, faded, faded, 255])
+        } else {
+            pixels += 1;
+            Rgba([255, 0, 0, 255])
+        };
+        diff.put_pixel(x, y, pixel);
+    }
+
+    (diff, pixels)
+}
+
+// only exactly `1`, so `PANDA_BLESS=0` or an empty value doesn't rewrite anything
+fn blessing(value: Option<&OsStr>) -> bool {
+    value == Some(OsStr::new("1"))
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(path) => write!(
+                f,
+                "no golden image at {}, run with PANDA_BLESS=1 to create it",
+                path.display()
+            ),
+            Self::Io(why) => write!(f, "{}", why),
+            Self::Image(why) => write!(f, "{}", why),
+            Self::Size { expected, found } => write!(
+                f,
+                "expected a {}x{} frame but found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            Self::Mismatch { pixels, diff } => write!(
+                f,
+                "{} pixels don't match the golden image, see {}",
+                pixels,
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ImageError> for GoldenError {
+    fn from(value: ImageError) -> Self {
+        Self::Image(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{canvas::Color, geometry::Vec2};
+
+    fn golden(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name)
+    }
+
+    #[test]
+    fn golden_primitives() {
+        let mut canvas = Canvas::new(32, 32);
+        canvas.clear(Color::BLACK.pixel());
+        canvas.draw_rectangle(2, 2, 10, 6, Color::from(0xEC1A23).pixel());
+        canvas.draw_circle(22, 10, 7, Color::from(0x01ADEE).pixel());
+        canvas.draw_line(&Vec2::new(2.0, 30.0), &Vec2::new(29.0, 16.0), Color::WHITE.pixel());
+
+        assert_golden(&canvas, golden("primitives.png"), 0);
+    }
+
+    #[test]
+    fn golden_bless_only_on_one() {
+        assert!(blessing(Some(OsStr::new("1"))));
+        for value in [None, Some(""), Some("0"), Some("true")] {
+            assert!(!blessing(value.map(OsStr::new)));
+        }
+    }
+
+    #[test]
+    fn golden_mismatch() {
+        let mut expected = RgbaImage::new(2, 2);
+        expected.put_pixel(1, 1, Rgba([10, 10, 10, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(0, 0, Rgba([3, 0, 0, 0]));
+        actual.put_pixel(1, 1, Rgba([12, 10, 10, 255]));
+
+        assert_eq!(diff_images(&expected, &actual, 0).1, 2);
+        assert_eq!(diff_images(&expected, &actual, 2).1, 1);
+        assert_eq!(diff_images(&expected, &actual, 3).1, 0);
+    }
+}
@@ -0,0 +1,242 @@
+// run a panda without a window, one frame at a time!
+// handy for tests and servers without a display
+
+use bevy_ecs::{schedule::Schedule, world::World};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, WindowEvent},
+};
+
+use crate::{
+    app, create_world, error, events, locate_mouse, scaling,
+    geometry::Vec2,
+    input::{self, Recorder, Replay},
+    Duration, Input, Key, Panda, PandaError, PandaOptions, Stage, Time,
+};
+
+impl Panda {
+    pub fn headless(options: PandaOptions) -> Self {
+        Self::try_headless(options).expect("couldn't make the panda")
+    }
+
+    // checks the options like `try_new`, without a window there's nothing else to go wrong
+    pub fn try_headless(options: PandaOptions) -> Result<Self, PandaError> {
+        let world = create_world(&options);
+        Self::headless_with_world(options, world)
+    }
+
+    pub(crate) fn headless_with_world(
+        options: PandaOptions,
+        world: World,
+    ) -> Result<Self, PandaError> {
+        error::check_options(&options)?;
+        Ok(Self {
+            world,
+            display: None,
+            events: Vec::new(),
+            scale: options.scale,
+            size: (options.width * options.scale, options.height * options.scale),
+            started: false,
+            stopped: false,
+        })
+    }
+
+    // runs the schedule once in place of `Stage::Update`,
+    // as if `delta` had passed since the last frame
+    pub fn step(&mut self, schedule: &mut Schedule, delta: Duration) {
+        let update = std::mem::take(schedule);
+        let update = app::replace_stage(&mut self.world, Stage::Update, update);
+        self.update(delta);
+        *schedule = app::replace_stage(&mut self.world, Stage::Update, update);
+    }
+
+    // like `step`, with `fixed` in place of `Stage::FixedUpdate`
+    pub fn step_fixed(&mut self, schedule: &mut Schedule, fixed: &mut Schedule, delta: Duration) {
+        let fixed_update = std::mem::take(fixed);
+        let fixed_update = app::replace_stage(&mut self.world, Stage::FixedUpdate, fixed_update);
+        self.step(schedule, delta);
+        *fixed = app::replace_stage(&mut self.world, Stage::FixedUpdate, fixed_update);
+    }
+
+    // runs every stage for one frame, as if `delta` had passed since the last one,
+    // does nothing after `teardown`
+    pub fn update(&mut self, delta: Duration) {
+        if self.stopped {
+            return;
+        }
+        let events = std::mem::take(&mut self.events);
+        let mut delta = delta;
+        for event in &events {
+            if let WindowEvent::Resized(size) = event {
+                self.size = (size.width, size.height);
+            }
+        }
+        scaling::fill_canvas(&mut self.world, self.size, self.scale as f64);
+
+        if self.world.contains_resource::<Replay>() {
+            let window: Vec<_> = events.iter().filter_map(input::passthrough).collect();
+            delta = input::replay_frame(&mut self.world, &window).unwrap_or(delta);
+        } else {
+            for event in &events {
+                events::send_window_event(&mut self.world, event);
+            }
+            if let Some(mut recorder) = self.world.get_resource_mut::<Recorder>() {
+                events.iter().for_each(|event| recorder.capture(event));
+            }
+
+            let mut input = self.world.resource_mut::<Input>();
+            input.step_with_window_events(&events);
+            locate_mouse(&mut self.world, self.size);
+        }
+        self.world.resource_mut::<Time>().update(delta);
+        input::record_frame(&mut self.world, delta);
+
+        app::run_frame(&mut self.world, &mut self.started);
+    }
+
+    // runs `Stage::Teardown` and saves the `Recorder`'s file, only the first time
+    pub fn teardown(&mut self) {
+        app::stop(&mut self.world, &mut self.stopped);
+    }
+
+    pub fn run_frames(&mut self, schedule: &mut Schedule, frames: u32, delta: Duration) {
+        for _ in 0..frames {
+            self.step(schedule, delta);
+        }
+    }
+
+    pub fn update_frames(&mut self, frames: u32, delta: Duration) {
+        for _ in 0..frames {
+            self.update(delta);
+        }
+    }
+
+    // queue a window event to be seen by `Input` on the next step
+    pub fn send_event(&mut self, event: WindowEvent<'static>) {
+        self.events.push(event);
+    }
+
+    // keys stay held until released, just like a real keyboard
+    pub fn press_key(&mut self, key: Key) {
+        self.send_event(keyboard_input(key, ElementState::Pressed));
+    }
+
+    pub fn release_key(&mut self, key: Key) {
+        self.send_event(keyboard_input(key, ElementState::Released));
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.send_event(mouse_input(button, ElementState::Pressed));
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.send_event(mouse_input(button, ElementState::Released));
+    }
+
+    // in window pixels, `PandaOptions::scale` times the canvas
+    pub fn move_mouse(&mut self, position: Vec2) {
+        #[allow(deprecated)]
+        self.send_event(WindowEvent::CursorMoved {
+            device_id: device_id(),
+            position: PhysicalPosition::new(position.x as f64, position.y as f64),
+            modifiers: ModifiersState::empty(),
+        });
+    }
+
+    pub fn type_char(&mut self, char: char) {
+        self.send_event(WindowEvent::ReceivedCharacter(char));
+    }
+}
+
+pub(crate) fn device_id() -> DeviceId {
+    // SAFETY: scripted events only go to `WinitInputHelper`, never back to winit
+    unsafe { DeviceId::dummy() }
+}
+
+#[allow(deprecated)]
+pub(crate) fn keyboard_input(key: Key, state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::KeyboardInput {
+        device_id: device_id(),
+        input: KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(key),
+            modifiers: ModifiersState::empty(),
+        },
+        is_synthetic: true,
+    }
+}
+
+#[allow(deprecated)]
+pub(crate) fn mouse_input(button: MouseButton, state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::MouseInput {
+        device_id: device_id(),
+        state,
+        button,
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::system::{Res, ResMut, Resource};
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Jumps(u32);
+
+    fn count_jumps(mut jumps: ResMut<Jumps>, input: Res<Input>) {
+        if input.key_pressed(Key::Space) {
+            jumps.0 += 1;
+        }
+    }
+
+    #[test]
+    fn headless_step() {
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.init_resource::<Jumps>();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(count_jumps);
+
+        panda.press_key(Key::Space);
+        panda.run_frames(&mut schedule, 10, Duration::from_millis(16));
+        assert_eq!(panda.world.resource::<Jumps>().0, 1);
+        assert!(panda.world.resource::<Input>().key_held(Key::Space));
+        assert_eq!(**panda.world.resource::<Time>(), Duration::from_millis(16));
+        assert_eq!(panda.world.resource::<Time>().frame(), 10);
+
+        panda.release_key(Key::Space);
+        panda.press_key(Key::Space);
+        panda.step(&mut schedule, Duration::from_millis(16));
+        assert_eq!(panda.world.resource::<Jumps>().0, 2);
+    }
+
+    #[test]
+    fn headless_mouse() {
+        let mut panda = Panda::headless(PandaOptions {
+            width: 10,
+            height: 10,
+            scale: 3,
+            ..PandaOptions::default()
+        });
+        let mut schedule = Schedule::new();
+
+        panda.move_mouse(Vec2::new(7.0, 29.5));
+        panda.press_mouse(MouseButton::Left);
+        panda.step(&mut schedule, Duration::from_millis(16));
+        let input = panda.world.resource::<Input>();
+        assert_eq!(input.mouse_canvas(), Some(Vec2::new(2.0, 9.0)));
+        assert!(input.button_pressed(MouseButton::Left));
+        assert!(!input.button_held(MouseButton::Right));
+
+        panda.move_mouse(Vec2::new(40.0, -2.0));
+        panda.release_mouse(MouseButton::Left);
+        panda.step(&mut schedule, Duration::from_millis(16));
+        let input = panda.world.resource::<Input>();
+        assert_eq!(input.mouse_canvas(), None);
+        assert_eq!(input.mouse_canvas_clamped(), Some(Vec2::new(9.0, 0.0)));
+        assert!(input.button_released(MouseButton::Left));
+    }
+}
@@ -0,0 +1,93 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy_ecs::system::Resource;
+use winit_input_helper::WinitInputHelper;
+
+use crate::{geometry::Vec2, MouseButton};
+
+mod map;
+mod record;
+
+pub use map::{Action, Binding, InputMap, InputMapError};
+pub use record::{InputEvent, RecordedFrame, Recorder, Recording, RecordingError, Replay};
+
+pub(crate) use record::{finish_recording, passthrough, record_frame, replay_frame};
+
+// everything `WinitInputHelper` knows, plus where the mouse is on the canvas
+#[derive(Resource)]
+pub struct Input {
+    helper: WinitInputHelper,
+    // both worked out by the panda every frame
+    mouse_canvas: Option<Vec2>,
+    mouse_clamped: Option<Vec2>,
+}
+
+impl Input {
+    pub(crate) fn new() -> Self {
+        Self {
+            helper: WinitInputHelper::new(),
+            mouse_canvas: None,
+            mouse_clamped: None,
+        }
+    }
+
+    // what used to be `input.0`, everything derefs to it too
+    pub fn helper(&self) -> &WinitInputHelper {
+        &self.helper
+    }
+
+    // the canvas pixel under the mouse, `None` when it's off the canvas
+    pub fn mouse_canvas(&self) -> Option<Vec2> {
+        self.mouse_canvas
+    }
+
+    // the nearest canvas pixel, `None` only when the mouse is outside the window
+    pub fn mouse_canvas_clamped(&self) -> Option<Vec2> {
+        self.mouse_clamped
+    }
+
+    pub fn button_pressed(&self, button: MouseButton) -> bool {
+        self.helper.mouse_pressed(button_index(button))
+    }
+
+    pub fn button_held(&self, button: MouseButton) -> bool {
+        self.helper.mouse_held(button_index(button))
+    }
+
+    pub fn button_released(&self, button: MouseButton) -> bool {
+        self.helper.mouse_released(button_index(button))
+    }
+
+    // `locate` turns a window position into a clamped canvas pixel,
+    // and says whether it was on the canvas to begin with
+    pub(crate) fn locate_mouse(&mut self, locate: impl Fn(Vec2) -> (Vec2, bool)) {
+        let located = self.helper.mouse().map(|(x, y)| locate(Vec2::new(x, y)));
+        self.mouse_clamped = located.map(|(pixel, _)| pixel);
+        self.mouse_canvas = located
+            .filter(|(_, inside)| *inside)
+            .map(|(pixel, _)| pixel);
+    }
+}
+
+// the numbering `WinitInputHelper` uses
+fn button_index(button: MouseButton) -> usize {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Other(button) => button as usize,
+    }
+}
+
+impl Deref for Input {
+    type Target = WinitInputHelper;
+    fn deref(&self) -> &Self::Target {
+        &self.helper
+    }
+}
+
+impl DerefMut for Input {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.helper
+    }
+}
@@ -0,0 +1,293 @@
+// game actions bound to keys and buttons, so players can rebind them
+
+use std::{collections::HashMap, fmt, fs, hash::Hash, io, path::Path};
+
+use bevy_ecs::system::Resource;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::Input;
+use crate::{Key, MouseButton};
+
+// anything that can name an action, usually a fieldless enum
+pub trait Action: Copy + Eq + Hash + Send + Sync + 'static {}
+
+impl<T: Copy + Eq + Hash + Send + Sync + 'static> Action for T {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+    // every key held together, like ctrl + s
+    Chord(Vec<Key>),
+}
+
+impl Binding {
+    // chords are pressed when their last key goes down
+    pub fn pressed(&self, input: &Input) -> bool {
+        match self {
+            Self::Key(key) => input.key_pressed(*key),
+            Self::Mouse(button) => input.button_pressed(*button),
+            Self::Chord(keys) => self.held(input) && keys.iter().any(|key| input.key_pressed(*key)),
+        }
+    }
+
+    pub fn held(&self, input: &Input) -> bool {
+        match self {
+            Self::Key(key) => input.key_held(*key),
+            Self::Mouse(button) => input.button_held(*button),
+            Self::Chord(keys) => !keys.is_empty() && keys.iter().all(|key| input.key_held(*key)),
+        }
+    }
+
+    // chords are released when the first of their keys comes up
+    pub fn released(&self, input: &Input) -> bool {
+        match self {
+            Self::Key(key) => input.key_released(*key),
+            Self::Mouse(button) => input.button_released(*button),
+            Self::Chord(keys) => {
+                keys.iter().any(|key| input.key_released(*key))
+                    && keys
+                        .iter()
+                        .all(|key| input.key_held(*key) || input.key_released(*key))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputMapError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputMap<A: Action> {
+    bindings: HashMap<A, Vec<Binding>>,
+}
+
+impl<A: Action> Default for InputMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Action> InputMap<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // for building the default controls in one go
+    pub fn with(mut self, action: A, binding: Binding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: A, binding: &Binding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|bound| bound != binding);
+        }
+    }
+
+    // swaps every binding of `action`
+    pub fn rebind(&mut self, action: A, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    pub fn clear(&mut self, action: A) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn bindings(&self, action: A) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // the actions bound to `binding`, for spotting conflicts while rebinding
+    pub fn actions(&self, binding: &Binding) -> impl Iterator<Item = A> + '_ {
+        let binding = binding.clone();
+        self.bindings
+            .iter()
+            .filter(move |(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn pressed(&self, input: &Input, action: A) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.pressed(input))
+    }
+
+    pub fn held(&self, input: &Input, action: A) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.held(input))
+    }
+
+    pub fn released(&self, input: &Input, action: A) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.released(input))
+    }
+}
+
+// bindings are kept as json, like `{ "Jump": [{ "Key": "Space" }] }`
+impl<A: Action + Serialize + DeserializeOwned> InputMap<A> {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputMapError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputMapError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, InputMapError> {
+        Ok(serde_json::from_str(source)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, InputMapError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for InputMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "couldn't read bindings: {}", why),
+            Self::Json(why) => write!(f, "couldn't parse bindings: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for InputMapError {}
+
+impl From<io::Error> for InputMapError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for InputMapError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Duration, Panda, PandaOptions};
+    use bevy_ecs::schedule::Schedule;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum Control {
+        Jump,
+        Shoot,
+        Save,
+    }
+
+    fn controls() -> InputMap<Control> {
+        InputMap::new()
+            .with(Control::Jump, Binding::Key(Key::Space))
+            .with(Control::Jump, Binding::Key(Key::Up))
+            .with(Control::Shoot, Binding::Mouse(MouseButton::Left))
+            .with(Control::Save, Binding::Chord(vec![Key::LControl, Key::S]))
+    }
+
+    fn step(panda: &mut Panda) {
+        panda.step(&mut Schedule::new(), Duration::from_millis(16));
+    }
+
+    #[test]
+    fn input_map_actions() {
+        let map = controls();
+        let mut panda = Panda::headless(PandaOptions::default());
+
+        panda.press_key(Key::Up);
+        panda.press_mouse(MouseButton::Left);
+        step(&mut panda);
+        let input = panda.world.resource::<Input>();
+        assert!(map.pressed(input, Control::Jump));
+        assert!(map.held(input, Control::Jump));
+        assert!(map.pressed(input, Control::Shoot));
+        assert!(!map.held(input, Control::Save));
+
+        panda.release_key(Key::Up);
+        step(&mut panda);
+        let input = panda.world.resource::<Input>();
+        assert!(map.released(input, Control::Jump));
+        assert!(!map.held(input, Control::Jump));
+        assert!(map.held(input, Control::Shoot));
+    }
+
+    #[test]
+    fn input_map_chords() {
+        let map = controls();
+        let mut panda = Panda::headless(PandaOptions::default());
+
+        // s on its own isn't enough
+        panda.press_key(Key::S);
+        step(&mut panda);
+        assert!(!map.pressed(panda.world.resource(), Control::Save));
+
+        panda.release_key(Key::S);
+        panda.press_key(Key::LControl);
+        step(&mut panda);
+        assert!(!map.pressed(panda.world.resource(), Control::Save));
+
+        panda.press_key(Key::S);
+        step(&mut panda);
+        assert!(map.pressed(panda.world.resource(), Control::Save));
+        step(&mut panda);
+        let input = panda.world.resource::<Input>();
+        assert!(!map.pressed(input, Control::Save));
+        assert!(map.held(input, Control::Save));
+
+        panda.release_key(Key::LControl);
+        step(&mut panda);
+        let input = panda.world.resource::<Input>();
+        assert!(map.released(input, Control::Save));
+        assert!(!map.held(input, Control::Save));
+    }
+
+    #[test]
+    fn input_map_rebind_and_save() {
+        let mut map = controls();
+        map.unbind(Control::Jump, &Binding::Key(Key::Up));
+        map.rebind(Control::Shoot, vec![Binding::Key(Key::X)]);
+        assert_eq!(map.bindings(Control::Jump), [Binding::Key(Key::Space)]);
+        assert_eq!(
+            map.actions(&Binding::Key(Key::X)).collect::<Vec<_>>(),
+            [Control::Shoot]
+        );
+
+        let path = std::env::temp_dir().join("panda_input_map.json");
+        map.save(&path).unwrap();
+        let loaded = InputMap::<Control>::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, map);
+
+        let json = r#"{ "Jump": [{ "Key": "W" }, { "Chord": ["LShift", "Space"] }] }"#;
+        let map = InputMap::<Control>::from_json(json).unwrap();
+        assert_eq!(map.bindings(Control::Jump).len(), 2);
+        assert!(map.bindings(Control::Shoot).is_empty());
+
+        assert!(matches!(
+            InputMap::<Control>::from_json(r#"{ "Fly": [] }"#),
+            Err(InputMapError::Json(_))
+        ));
+        assert!(matches!(
+            InputMap::<Control>::load("missing.json"),
+            Err(InputMapError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,494 @@
+// record what the player did every frame, then play it back exactly
+// great for reproducing bugs and for demo modes
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy_ecs::{
+    system::Resource,
+    world::{Mut, World},
+};
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, MouseScrollDelta, TouchPhase, WindowEvent},
+};
+
+use super::Input;
+use crate::{
+    events,
+    geometry::Vec2,
+    headless::{device_id, keyboard_input, mouse_input},
+    Duration, Key, MouseButton,
+};
+
+// the window events `Input` cares about, without the bits that can't be saved
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key { key: Key, pressed: bool },
+    Mouse { button: MouseButton, pressed: bool },
+    // in window pixels
+    CursorMoved(Vec2),
+    Scroll { x: f32, y: f32, lines: bool },
+    Text(char),
+    Focused(bool),
+}
+
+impl InputEvent {
+    pub fn from_window(event: &WindowEvent) -> Option<Self> {
+        let pressed = |state: &ElementState| *state == ElementState::Pressed;
+        Some(match event {
+            WindowEvent::KeyboardInput { input, .. } => Self::Key {
+                key: input.virtual_keycode?,
+                pressed: pressed(&input.state),
+            },
+            WindowEvent::MouseInput { button, state, .. } => Self::Mouse {
+                button: *button,
+                pressed: pressed(state),
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                Self::CursorMoved(Vec2::new(position.x as f32, position.y as f32))
+            }
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => Self::Scroll {
+                    x: *x,
+                    y: *y,
+                    lines: true,
+                },
+                MouseScrollDelta::PixelDelta(position) => Self::Scroll {
+                    x: position.x as f32,
+                    y: position.y as f32,
+                    lines: false,
+                },
+            },
+            WindowEvent::ReceivedCharacter(char) => Self::Text(*char),
+            WindowEvent::Focused(focused) => Self::Focused(*focused),
+            _ => return None,
+        })
+    }
+
+    #[allow(deprecated)]
+    pub fn to_window(&self) -> WindowEvent<'static> {
+        let state = |pressed: bool| match pressed {
+            true => ElementState::Pressed,
+            false => ElementState::Released,
+        };
+        match *self {
+            Self::Key { key, pressed } => keyboard_input(key, state(pressed)),
+            Self::Mouse { button, pressed } => mouse_input(button, state(pressed)),
+            Self::CursorMoved(position) => WindowEvent::CursorMoved {
+                device_id: device_id(),
+                position: PhysicalPosition::new(position.x as f64, position.y as f64),
+                modifiers: Default::default(),
+            },
+            Self::Scroll { x, y, lines } => WindowEvent::MouseWheel {
+                device_id: device_id(),
+                delta: match lines {
+                    true => MouseScrollDelta::LineDelta(x, y),
+                    false => {
+                        MouseScrollDelta::PixelDelta(PhysicalPosition::new(x as f64, y as f64))
+                    }
+                },
+                phase: TouchPhase::Moved,
+                modifiers: Default::default(),
+            },
+            Self::Text(char) => WindowEvent::ReceivedCharacter(char),
+            Self::Focused(focused) => WindowEvent::Focused(focused),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub delta: Duration,
+    pub events: Vec<InputEvent>,
+    // saved as seen, so replays don't depend on the window size
+    pub mouse_canvas: Option<Vec2>,
+    pub mouse_clamped: Option<Vec2>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delta).sum()
+    }
+}
+
+// insert to record every frame from then on
+#[derive(Resource, Debug, Default)]
+pub struct Recorder {
+    recording: Recording,
+    // this frame's events so far
+    events: Vec<InputEvent>,
+    // saved here when the window closes
+    path: Option<PathBuf>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn to_file(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Self::default()
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording {
+        self.recording
+    }
+
+    pub(crate) fn capture(&mut self, event: &WindowEvent) {
+        self.events.extend(InputEvent::from_window(event));
+    }
+
+    fn end_frame(&mut self, delta: Duration, input: &Input) {
+        self.recording.frames.push(RecordedFrame {
+            delta,
+            events: std::mem::take(&mut self.events),
+            mouse_canvas: input.mouse_canvas,
+            mouse_clamped: input.mouse_clamped,
+        });
+    }
+}
+
+// insert to play a recording back instead of the player's input,
+// the window's own events like resizing still get through
+#[derive(Resource, Debug)]
+pub struct Replay {
+    recording: Recording,
+    frame: usize,
+    // start over at the end, for demo modes
+    pub looping: bool,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            looping: false,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Ok(Self::new(Recording::load(path)?))
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    // how many frames have been played
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.looping && self.frame >= self.recording.frames.len()
+    }
+
+    fn next(&mut self) -> Option<RecordedFrame> {
+        if self.looping && self.frame >= self.recording.frames.len() {
+            self.frame = 0;
+        }
+        let frame = self.recording.frames.get(self.frame)?.clone();
+        self.frame += 1;
+        Some(frame)
+    }
+}
+
+// the window events a replay lets through
+pub(crate) fn passthrough(event: &WindowEvent) -> Option<WindowEvent<'static>> {
+    match event {
+        WindowEvent::CloseRequested => Some(WindowEvent::CloseRequested),
+        WindowEvent::Destroyed => Some(WindowEvent::Destroyed),
+        WindowEvent::Resized(size) => Some(WindowEvent::Resized(*size)),
+        WindowEvent::DroppedFile(path) => Some(WindowEvent::DroppedFile(path.clone())),
+        _ => None,
+    }
+}
+
+// moves `Input` on by the next recorded frame, returning its delta,
+// once the replay runs out only the window's events are left
+pub(crate) fn replay_frame(world: &mut World, window: &[WindowEvent<'static>]) -> Option<Duration> {
+    let frame = world.resource_mut::<Replay>().next();
+
+    let mut events = window.to_vec();
+    if let Some(frame) = &frame {
+        events.extend(frame.events.iter().map(InputEvent::to_window));
+        if let Some(mut recorder) = world.get_resource_mut::<Recorder>() {
+            recorder.events.extend(frame.events.iter().cloned());
+        }
+    }
+    for event in &events {
+        events::send_window_event(world, event);
+    }
+
+    let mut input = world.resource_mut::<Input>();
+    input.step_with_window_events(&events);
+    if let Some(frame) = &frame {
+        input.mouse_canvas = frame.mouse_canvas;
+        input.mouse_clamped = frame.mouse_clamped;
+    }
+    frame.map(|frame| frame.delta)
+}
+
+pub(crate) fn record_frame(world: &mut World, delta: Duration) {
+    world.resource_scope(|world, input: Mut<Input>| {
+        if let Some(mut recorder) = world.get_resource_mut::<Recorder>() {
+            recorder.end_frame(delta, &input);
+        }
+    });
+}
+
+// saves the recording if it was asked to be, when the panda stops
+pub(crate) fn finish_recording(world: &World) -> Result<(), RecordingError> {
+    match world.get_resource::<Recorder>() {
+        Some(Recorder {
+            path: Some(path),
+            recording,
+            ..
+        }) => recording.save(path),
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "couldn't read or write recording: {}", why),
+            Self::Json(why) => write!(f, "couldn't parse recording: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<io::Error> for RecordingError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for RecordingError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::Schedule,
+        system::{Res, ResMut},
+    };
+    use winit_input_helper::TextChar;
+
+    use super::*;
+    use crate::{ErrorHook, OnError, Panda, PandaError, PandaOptions, Time};
+
+    // everything a system could see, frame by frame
+    #[derive(Resource, Default)]
+    struct Seen(Vec<(Duration, bool, bool, Option<Vec2>, String)>);
+
+    fn watch(mut seen: ResMut<Seen>, input: Res<Input>, time: Res<Time>) {
+        seen.0.push((
+            time.delta(),
+            input.key_pressed(Key::Space),
+            input.button_held(MouseButton::Left),
+            input.mouse_canvas(),
+            input
+                .text()
+                .into_iter()
+                .filter_map(|text| match text {
+                    TextChar::Char(char) => Some(char),
+                    TextChar::Back => None,
+                })
+                .collect(),
+        ));
+    }
+
+    fn play(panda: &mut Panda) -> Vec<(Duration, bool, bool, Option<Vec2>, String)> {
+        panda.world.init_resource::<Seen>();
+        let mut schedule = Schedule::new();
+        schedule.add_system(watch);
+
+        panda.press_key(Key::Space);
+        panda.step(&mut schedule, Duration::from_millis(16));
+        panda.move_mouse(Vec2::new(12.0, 7.0));
+        panda.press_mouse(MouseButton::Left);
+        panda.type_char('x');
+        panda.step(&mut schedule, Duration::from_millis(20));
+        panda.release_key(Key::Space);
+        panda.release_mouse(MouseButton::Left);
+        panda.step(&mut schedule, Duration::from_millis(17));
+        std::mem::take(&mut panda.world.resource_mut::<Seen>().0)
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.insert_resource(Recorder::new());
+        let seen = play(&mut panda);
+        let recording = panda
+            .world
+            .remove_resource::<Recorder>()
+            .unwrap()
+            .into_recording();
+        assert_eq!(recording.frames.len(), 3);
+        assert_eq!(recording.duration(), Duration::from_millis(53));
+
+        // the replay wins over anything the panda is told
+        let mut panda = Panda::headless(PandaOptions {
+            scale: 1,
+            ..PandaOptions::default()
+        });
+        panda.world.insert_resource(Replay::new(recording.clone()));
+        panda.press_key(Key::A);
+        let mut schedule = Schedule::new();
+        panda.world.init_resource::<Seen>();
+        schedule.add_system(watch);
+        panda.run_frames(&mut schedule, 3, Duration::from_secs(1));
+        assert_eq!(panda.world.resource::<Seen>().0, seen);
+        assert!(!panda.world.resource::<Input>().key_held(Key::A));
+        assert!(panda.world.resource::<Replay>().finished());
+
+        // and recording a replay gives the same recording back
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.insert_resource(Replay::new(recording.clone()));
+        panda.world.insert_resource(Recorder::new());
+        panda.run_frames(&mut Schedule::new(), 3, Duration::from_secs(1));
+        assert_eq!(panda.world.resource::<Recorder>().recording(), &recording);
+    }
+
+    #[test]
+    fn replay_looping() {
+        let frame = |pressed| RecordedFrame {
+            delta: Duration::from_millis(10),
+            events: vec![InputEvent::Key {
+                key: Key::A,
+                pressed,
+            }],
+            ..RecordedFrame::default()
+        };
+        let mut replay = Replay::new(Recording {
+            frames: vec![frame(true), frame(false)],
+        });
+        replay.looping = true;
+
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.insert_resource(replay);
+        let mut schedule = Schedule::new();
+        panda.run_frames(&mut schedule, 3, Duration::from_secs(1));
+        assert!(panda.world.resource::<Input>().key_pressed(Key::A));
+        assert_eq!(**panda.world.resource::<Time>(), Duration::from_millis(10));
+        assert!(!panda.world.resource::<Replay>().finished());
+
+        // a finished replay leaves the real delta alone
+        panda.world.resource_mut::<Replay>().looping = false;
+        panda.run_frames(&mut schedule, 2, Duration::from_secs(1));
+        assert!(panda.world.resource::<Replay>().finished());
+        assert_eq!(
+            panda.world.resource::<Time>().raw_delta(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn recording_files() {
+        let recording = Recording {
+            frames: vec![RecordedFrame {
+                delta: Duration::from_millis(16),
+                events: vec![
+                    InputEvent::Key {
+                        key: Key::Left,
+                        pressed: true,
+                    },
+                    InputEvent::CursorMoved(Vec2::new(3.0, 4.5)),
+                    InputEvent::Scroll {
+                        x: 0.0,
+                        y: -1.0,
+                        lines: true,
+                    },
+                ],
+                mouse_canvas: Some(Vec2::new(1.0, 1.0)),
+                mouse_clamped: Some(Vec2::new(1.0, 1.0)),
+            }],
+        };
+
+        let path = std::env::temp_dir().join("panda_recording.json");
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, recording);
+
+        for event in &recording.frames[0].events {
+            assert_eq!(
+                InputEvent::from_window(&event.to_window()).as_ref(),
+                Some(event)
+            );
+        }
+        assert!(matches!(
+            Recording::load("missing.json"),
+            Err(RecordingError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn recording_saved_on_teardown() {
+        let path = std::env::temp_dir().join("panda_teardown_recording.json");
+        let mut panda = Panda::headless(PandaOptions::default());
+        panda.world.insert_resource(Recorder::to_file(&path));
+        panda.press_key(Key::Space);
+        panda.update_frames(3, Duration::from_millis(16));
+        panda.teardown();
+
+        let saved = Recording::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.frames.len(), 3);
+        assert_eq!(saved, *panda.world.resource::<Recorder>().recording());
+
+        // failing to save goes to the error hook
+        let mut panda = Panda::headless(PandaOptions::default());
+        let (send, errors) = std::sync::mpsc::channel();
+        panda.world.insert_resource(ErrorHook::new(move |error| {
+            send.send(matches!(error, PandaError::Recording(_)))
+                .unwrap();
+            OnError::Exit
+        }));
+        panda.world.insert_resource(Recorder::to_file(
+            std::env::temp_dir().join("no/such/dir.json"),
+        ));
+        panda.update(Duration::ZERO);
+        panda.teardown();
+        assert_eq!(errors.try_iter().collect::<Vec<_>>(), [true]);
+    }
+}
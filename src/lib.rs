@@ -1,35 +1,61 @@
 use bevy_ecs::{
     schedule::Schedule,
-    system::Resource,
     world::World,
 };
-use pixels::{Pixels, SurfaceTexture};
-use std::{time::Instant, ops::{Deref, DerefMut}};
+use pixels::{wgpu::RequestAdapterOptions, Pixels, PixelsBuilder, SurfaceTexture};
+use std::time::Instant;
 use winit::{
     dpi::LogicalSize,
-    event::Event,
+    event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::{Window, WindowBuilder},
 };
-use winit_input_helper::WinitInputHelper;
 
 pub use std::time::Duration;
-pub use winit::event::VirtualKeyCode as Key;
+pub use winit::event::{MouseButton, VirtualKeyCode as Key};
 pub use glam;
 
+pub mod animation;
+pub mod app;
+pub mod aseprite;
 pub mod geometry;
+pub mod blend;
+pub mod camera;
 pub mod canvas;
+pub mod error;
+pub mod events;
+pub mod fixed;
+pub mod golden;
+pub mod input;
+pub mod sheet;
+pub mod scaling;
 pub mod sprite;
+pub mod state;
+pub mod text;
+pub mod time;
+pub mod tiled;
+pub mod tilemap;
 pub mod utils;
 
+mod headless;
 mod line;
 
+pub use app::{PandaBuilder, Plugin, Stage};
+pub use camera::Camera;
 pub use canvas::Canvas;
+pub use error::{ErrorHook, OnError, PandaError};
+pub use fixed::FixedTime;
+pub use input::Input;
+pub use time::Time;
+
+use input::{Recorder, Replay};
+use scaling::Scaling;
 
 pub struct PandaOptions<'a> {
     pub title: &'a str,
     pub width: u32,
     pub height: u32,
+    // window pixels per canvas pixel, when the window opens
     pub scale: u32,
 }
 
@@ -44,20 +70,41 @@ impl Default for PandaOptions<'_> {
     }
 }
 
-#[derive(Resource)]
-pub struct Input(pub WinitInputHelper);
-
-#[derive(Resource)]
-pub struct Time(pub Duration);
-
 pub struct Panda {
     pub world: World,
+    // `None` for headless pandas
+    display: Option<Display>,
+    // scripted events for the next headless step
+    events: Vec<WindowEvent<'static>>,
+    // window pixels per canvas pixel, for `ScalingMode::Fill`
+    scale: u32,
+    // the pretend window of headless pandas, in window pixels
+    size: (u32, u32),
+    // whether `Stage::Startup` and `Stage::Teardown` have run
+    started: bool,
+    stopped: bool,
+}
+
+struct Display {
     event_loop: EventLoop<()>,
     window: Window,
+    pixels: Pixels,
 }
 
 impl Panda {
     pub fn new(options: PandaOptions) -> Self {
+        Self::try_new(options).expect("couldn't open the panda")
+    }
+
+    // errors come straight back, there's no `ErrorHook` in a new world yet,
+    // so falling back to a software adapter needs `PandaBuilder::try_build` with one inserted
+    pub fn try_new(options: PandaOptions) -> Result<Self, PandaError> {
+        let world = create_world(&options);
+        Self::with_world(options, world)
+    }
+
+    fn with_world(options: PandaOptions, mut world: World) -> Result<Self, PandaError> {
+        error::check_options(&options)?;
         let event_loop = EventLoop::new();
 
         let window = {
@@ -68,87 +115,238 @@ impl Panda {
             WindowBuilder::new()
                 .with_title(options.title)
                 .with_inner_size(size)
-                .build(&event_loop)
-                .unwrap()
+                .build(&event_loop)?
         };
 
-        let input = WinitInputHelper::new();
-
-        let pixels = {
-            let size = window.inner_size();
-            let surface = SurfaceTexture::new(size.width, size.height, &window);
-            Pixels::new(options.width, options.height, surface).unwrap()
+        // the hook gets one chance to fall back to a software adapter
+        let pixels = match create_pixels(&window, options.width, options.height, false) {
+            Err(why) if world.contains_resource::<ErrorHook>() => {
+                let error = PandaError::Surface(why);
+                match error::handle_error(&mut world, &error) {
+                    OnError::Fallback => {
+                        create_pixels(&window, options.width, options.height, true)
+                            .map_err(PandaError::Surface)?
+                    }
+                    _ => return Err(error),
+                }
+            }
+            pixels => pixels.map_err(PandaError::Surface)?,
         };
 
+        Ok(Self {
+            world,
+            display: Some(Display {
+                event_loop,
+                window,
+                pixels,
+            }),
+            events: Vec::new(),
+            scale: options.scale,
+            size: (options.width * options.scale, options.height * options.scale),
+            started: false,
+            stopped: false,
+        })
+    }
 
-        let mut world = World::new();
-        world.insert_resource(Input(input));
-        world.insert_resource(Canvas(pixels));
-        world.insert_resource(Time(Duration::default()));
+    // runs `schedule` as `Stage::Update`, replacing any update systems a builder added
+    pub fn run(mut self, schedule: Schedule) {
+        app::replace_stage(&mut self.world, Stage::Update, schedule);
+        self.start();
+    }
 
-        Self {
+    // `fixed` runs at the rate in the `FixedTime` resource, before `schedule` each frame
+    pub fn run_fixed(mut self, schedule: Schedule, fixed: Schedule) {
+        app::replace_stage(&mut self.world, Stage::FixedUpdate, fixed);
+        self.run(schedule);
+    }
+
+    fn start(self) {
+        let Display {
             event_loop,
             window,
-            world,
-        }
-    }
+            mut pixels,
+        } = self
+            .display
+            .expect("headless pandas can't open a window x_x, use `step` instead");
+        let mut world = self.world;
+        let mut started = self.started;
+        let mut stopped = self.stopped;
+        let scale = self.scale;
+        // the size `pixels` is drawing, the canvas or the whole window when scaled smoothly
+        let mut frame_size = canvas_size(&world);
 
-    pub fn run(mut self, mut schedule: Schedule) {
-        let mut old = Instant::now();
-        self.event_loop.run(move |event, _, control_flow| {
-            if self.world.resource_mut::<Input>().0.update(&event) {
+        // `None` until the first frame, which shouldn't count the time spent setting up
+        let mut old: Option<Instant> = None;
+        // what the window did this frame, while a replay stands in for the input
+        let mut replayed = Vec::new();
+        event_loop.run(move |event, _, control_flow| {
+            if let Event::LoopDestroyed = event {
+                app::stop(&mut world, &mut stopped);
+                return;
+            }
+            // winit keeps handing over the rest of this loop's events after `Exit`
+            if stopped {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+
+            let replaying = world.contains_resource::<Replay>();
+            if let Event::WindowEvent { event, .. } = &event {
+                if replaying {
+                    replayed.extend(input::passthrough(event));
+                } else {
+                    events::send_window_event(&mut world, event);
+                    if let Some(mut recorder) = world.get_resource_mut::<Recorder>() {
+                        recorder.capture(event);
+                    }
+                }
+            }
+
+            let frame = match replaying {
+                true => matches!(event, Event::MainEventsCleared),
+                false => world.resource_mut::<Input>().update(&event),
+            };
+
+            if frame {
                 let now = Instant::now();
-                self.world.resource_mut::<Time>().0 = now.duration_since(old);
-                old = now;
+                let mut delta = old.map_or(Duration::ZERO, |old| now.duration_since(old));
+                old = Some(now);
 
-                schedule.run(&mut self.world);
-                self.window.request_redraw();
+                let size = window.inner_size();
+                let size = (size.width, size.height);
+                scaling::fill_canvas(&mut world, size, scale as f64 * window.scale_factor());
+
+                if replaying {
+                    let window = std::mem::take(&mut replayed);
+                    delta = input::replay_frame(&mut world, &window).unwrap_or(delta);
+                } else {
+                    locate_mouse(&mut world, size);
+                }
+
+                world.resource_mut::<Time>().update(delta);
+                input::record_frame(&mut world, delta);
+
+                app::run_frame(&mut world, &mut started);
+                window.request_redraw();
             }
 
-            if self.world.resource_mut::<Input>().0.close_requested() {
+            if world.resource::<Input>().close_requested() {
+                app::stop(&mut world, &mut stopped);
                 *control_flow = ControlFlow::Exit;
+                return;
             }
 
-            if let Some(size) = self.world.resource_mut::<Input>().0.window_resized() {
-                if let Err(why) = self.world.resource_mut::<Canvas>().0.resize_surface(size.width, size.height) {
-                    *control_flow = ControlFlow::Exit;
-                    eprintln!("{}", why);
+            let mut error = None;
+            if let Some(size) = world.resource::<Input>().window_resized() {
+                if let Err(why) = pixels.resize_surface(size.width, size.height) {
+                    error = Some(PandaError::Resize(why));
+                }
+            }
+
+            let size = window.inner_size();
+            let minimized = size.width == 0 || size.height == 0;
+            if let (Event::RedrawRequested(_), false) = (&event, minimized) {
+                let size = (size.width, size.height);
+                let canvas = world.resource::<Canvas>();
+                let scaling = world.resource::<Scaling>();
+                let smooth = scaling.mode.smooth();
+
+                let wanted = match smooth {
+                    true => size,
+                    false => canvas_size(&world),
+                };
+                if wanted != frame_size {
+                    match pixels.resize_buffer(wanted.0, wanted.1) {
+                        Ok(()) => frame_size = wanted,
+                        Err(why) => error = Some(PandaError::Resize(why)),
+                    }
+                }
+
+                if error.is_none() {
+                    pixels.clear_color(scaling.letterbox.into());
+                    if smooth {
+                        let viewport = scaling.mode.viewport(canvas_size(&world), size);
+                        let frame = pixels.frame_mut();
+                        scaling::draw_scaled(canvas, viewport, size, scaling.letterbox, frame);
+                    } else {
+                        pixels.frame_mut().copy_from_slice(canvas.frame());
+                    }
+
+                    if let Err(why) = pixels.render() {
+                        error = Some(PandaError::Render(why));
+                    }
                 }
             }
 
-            if let Event::RedrawRequested(_) = event {
-                if let Err(why) = self.world.resource_mut::<Canvas>().0.render() {
+            if let Some(error) = error {
+                let recovered = match error::handle_error(&mut world, &error) {
+                    OnError::Exit => false,
+                    OnError::Continue => true,
+                    OnError::Fallback => {
+                        let canvas = world.resource::<Canvas>();
+                        match create_pixels(&window, canvas.width(), canvas.height(), true) {
+                            Ok(fallback) => {
+                                pixels = fallback;
+                                frame_size = canvas_size(&world);
+                                true
+                            }
+                            // nothing left to fall back to
+                            Err(why) => {
+                                error::handle_error(&mut world, &PandaError::Surface(why));
+                                false
+                            }
+                        }
+                    }
+                };
+                if !recovered {
+                    app::stop(&mut world, &mut stopped);
                     *control_flow = ControlFlow::Exit;
-                    eprintln!("{}", why);
                 }
             }
         })
     }
 }
 
-impl Deref for Input {
-    type Target = WinitInputHelper;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+fn canvas_size(world: &World) -> (u32, u32) {
+    let canvas = world.resource::<Canvas>();
+    (canvas.width(), canvas.height())
 }
 
-impl DerefMut for Input {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
+// finds the canvas pixel under the mouse in a `window` sized window
+fn locate_mouse(world: &mut World, window: (u32, u32)) {
+    let canvas = canvas_size(world);
+    let viewport = world.resource::<Scaling>().mode.viewport(canvas, window);
+    world
+        .resource_mut::<Input>()
+        .locate_mouse(|position| viewport.to_canvas(position, canvas));
 }
 
-impl Deref for Time {
-    type Target = Duration;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+fn create_pixels(
+    window: &Window,
+    width: u32,
+    height: u32,
+    fallback: bool,
+) -> Result<Pixels, pixels::Error> {
+    let size = window.inner_size();
+    let surface = SurfaceTexture::new(size.width, size.height, window);
+    PixelsBuilder::new(width, height, surface)
+        .request_adapter_options(RequestAdapterOptions {
+            force_fallback_adapter: fallback,
+            ..RequestAdapterOptions::default()
+        })
+        .build()
 }
 
-impl DerefMut for Time {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
+fn create_world(options: &PandaOptions) -> World {
+    let mut world = World::new();
+    world.insert_resource(Input::new());
+    world.insert_resource(Canvas::new(options.width, options.height));
+    world.insert_resource(Camera::new(options.width, options.height));
+    world.insert_resource(Scaling::default());
+    world.insert_resource(Time::new());
+    world.insert_resource(FixedTime::default());
+    app::add_stages(&mut world);
+    events::add_window_events(&mut world);
+    world
 }
 
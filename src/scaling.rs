@@ -0,0 +1,270 @@
+// how the canvas fills a window that isn't exactly `PandaOptions::scale` times its size
+
+use bevy_ecs::{system::Resource, world::World};
+
+use crate::{
+    canvas::{Canvas, Color},
+    geometry::Vec2,
+    Camera,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScalingMode {
+    // whole multiples of the canvas only, so every pixel is the same size,
+    // with letterbox around the rest
+    #[default]
+    Integer,
+    // as big as fits without changing the shape, pixels can end up uneven
+    Fit,
+    // all of the window, squashing pixels when the shapes don't match
+    Stretch,
+    // the canvas grows and shrinks with the window, keeping pixels `scale` big,
+    // systems see the new `Canvas::width` and `Canvas::height`
+    Fill,
+}
+
+// change these any time, they're looked at every frame,
+// or insert your own on the builder to start with
+#[derive(Resource, Clone, Debug)]
+pub struct Scaling {
+    pub mode: ScalingMode,
+    // around the canvas when it doesn't cover the window
+    pub letterbox: Color,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self {
+            mode: ScalingMode::default(),
+            letterbox: Color::BLACK,
+        }
+    }
+}
+
+// where the canvas is drawn in the window, in window pixels,
+// it hangs off the edges when an integer scaled canvas is bigger than the window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScalingMode {
+    pub fn viewport(self, canvas: (u32, u32), window: (u32, u32)) -> Viewport {
+        let canvas_width = canvas.0.max(1) as f64;
+        let canvas_height = canvas.1.max(1) as f64;
+        let (window_width, window_height) = (window.0 as f64, window.1 as f64);
+        let fit = (window_width / canvas_width).min(window_height / canvas_height);
+
+        let (width, height) = match self {
+            Self::Integer | Self::Fill => {
+                let scale = fit.floor().max(1.0);
+                (canvas_width * scale, canvas_height * scale)
+            }
+            Self::Fit => (canvas_width * fit, canvas_height * fit),
+            Self::Stretch => (window_width, window_height),
+        };
+        Viewport {
+            x: ((window_width - width) / 2.0).floor() as i32,
+            y: ((window_height - height) / 2.0).floor() as i32,
+            width: width.round() as u32,
+            height: height.round() as u32,
+        }
+    }
+
+    // whether the canvas is scaled on the cpu into a window sized frame,
+    // instead of `pixels` scaling it by whole numbers
+    pub(crate) fn smooth(self) -> bool {
+        matches!(self, Self::Fit | Self::Stretch)
+    }
+}
+
+impl Viewport {
+    // the canvas pixel under a window position, clamped to the canvas, and whether it was inside
+    pub fn to_canvas(&self, position: Vec2, canvas: (u32, u32)) -> (Vec2, bool) {
+        let canvas = Vec2::new(canvas.0 as f32, canvas.1 as f32);
+        let size = Vec2::new(self.width.max(1) as f32, self.height.max(1) as f32);
+        let offset = Vec2::new(self.x as f32, self.y as f32);
+
+        let pixel = ((position - offset) * canvas / size).floor();
+        let clamped = pixel.clamp(Vec2::ZERO, (canvas - Vec2::ONE).max(Vec2::ZERO));
+        (clamped, clamped == pixel)
+    }
+}
+
+// for `ScalingMode::Fill`, `pixel_size` is how many window pixels a canvas pixel takes up
+pub(crate) fn fill_canvas(world: &mut World, window: (u32, u32), pixel_size: f64) {
+    if world.resource::<Scaling>().mode != ScalingMode::Fill {
+        return;
+    }
+    let pixel_size = pixel_size.max(1.0);
+    let width = ((window.0 as f64 / pixel_size) as u32).max(1);
+    let height = ((window.1 as f64 / pixel_size) as u32).max(1);
+
+    let mut canvas = world.resource_mut::<Canvas>();
+    if (canvas.width(), canvas.height()) != (width, height) {
+        canvas.resize(width, height);
+        if let Some(mut camera) = world.get_resource_mut::<Camera>() {
+            camera.resize(width, height);
+        }
+    }
+}
+
+// nearest neighbour scaling of the canvas into `frame`, which is `window` sized
+pub(crate) fn draw_scaled(
+    canvas: &Canvas,
+    viewport: Viewport,
+    window: (u32, u32),
+    letterbox: Color,
+    frame: &mut [u8],
+) {
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+    let (viewport_width, viewport_height) =
+        (viewport.width.max(1) as i64, viewport.height.max(1) as i64);
+    let source = canvas.frame();
+
+    // which canvas column each window column shows, if any
+    let columns: Vec<Option<usize>> = (0..window.0 as i64)
+        .map(|x| {
+            let x = x - viewport.x as i64;
+            (0..viewport_width)
+                .contains(&x)
+                .then(|| (x * width / viewport_width) as usize)
+        })
+        .collect();
+
+    for (y, row) in frame.chunks_exact_mut(4 * window.0 as usize).enumerate() {
+        let y = y as i64 - viewport.y as i64;
+        if !(0..viewport_height).contains(&y) {
+            row.chunks_exact_mut(4)
+                .for_each(|pixel| pixel.copy_from_slice(&letterbox.pixel()));
+            continue;
+        }
+        let start = (y * height / viewport_height * width) as usize;
+        for (pixel, column) in row.chunks_exact_mut(4).zip(&columns) {
+            match column {
+                Some(x) => pixel.copy_from_slice(&source[4 * (start + x)..4 * (start + x + 1)]),
+                None => pixel.copy_from_slice(&letterbox.pixel()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+    use super::*;
+    use crate::{Duration, Panda, PandaOptions};
+
+    #[test]
+    fn viewports() {
+        let canvas = (100, 50);
+        let window = (350, 200);
+        assert_eq!(
+            ScalingMode::Integer.viewport(canvas, window),
+            Viewport {
+                x: 25,
+                y: 25,
+                width: 300,
+                height: 150
+            }
+        );
+        assert_eq!(
+            ScalingMode::Fit.viewport(canvas, window),
+            Viewport {
+                x: 0,
+                y: 12,
+                width: 350,
+                height: 175
+            }
+        );
+        assert_eq!(
+            ScalingMode::Stretch.viewport(canvas, window),
+            Viewport {
+                x: 0,
+                y: 0,
+                width: 350,
+                height: 200
+            }
+        );
+        // too small for even one whole scale
+        assert_eq!(
+            ScalingMode::Integer.viewport(canvas, (80, 50)),
+            Viewport {
+                x: -10,
+                y: 0,
+                width: 100,
+                height: 50
+            }
+        );
+
+        let viewport = ScalingMode::Integer.viewport(canvas, window);
+        assert_eq!(
+            viewport.to_canvas(Vec2::new(28.0, 30.0), canvas),
+            (Vec2::new(1.0, 1.0), true)
+        );
+        assert_eq!(
+            viewport.to_canvas(Vec2::new(10.0, 190.0), canvas),
+            (Vec2::new(0.0, 49.0), false)
+        );
+    }
+
+    #[test]
+    fn scaled_letterbox() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.draw_pixel(1, 0, Color::WHITE.pixel());
+        let red = Color::new(255, 0, 0, 255);
+
+        let window = (6, 4);
+        let viewport = ScalingMode::Fit.viewport((2, 1), window);
+        let mut frame = vec![0; 4 * 6 * 4];
+        draw_scaled(&canvas, viewport, window, red, &mut frame);
+
+        let pixels: String = frame
+            .chunks_exact(4)
+            .map(|pixel| match pixel {
+                [255, 255, 255, 255] => '#',
+                [255, 0, 0, 255] => 'r',
+                _ => '.',
+            })
+            .collect();
+        assert_eq!(pixels, "...###...###...###rrrrrr");
+    }
+
+    #[test]
+    fn fill_resizes_canvas() {
+        let mut panda = Panda::builder(PandaOptions {
+            width: 20,
+            height: 10,
+            scale: 2,
+            ..PandaOptions::default()
+        })
+        .insert_resource(Scaling {
+            mode: ScalingMode::Fill,
+            ..Scaling::default()
+        })
+        .build_headless();
+        panda.update(Duration::ZERO);
+        assert_eq!(panda.world.resource::<Canvas>().width(), 20);
+
+        panda.send_event(WindowEvent::Resized(PhysicalSize::new(64, 30)));
+        panda.update(Duration::ZERO);
+        let canvas = panda.world.resource::<Canvas>();
+        assert_eq!((canvas.width(), canvas.height()), (32, 15));
+        assert_eq!(
+            panda.world.resource::<Camera>().size(),
+            Vec2::new(32.0, 15.0)
+        );
+
+        // the mouse lands on the bigger canvas
+        panda.move_mouse(Vec2::new(62.0, 2.0));
+        panda.update(Duration::ZERO);
+        assert_eq!(
+            panda.world.resource::<crate::Input>().mouse_canvas(),
+            Some(Vec2::new(31.0, 1.0))
+        );
+    }
+}
@@ -0,0 +1,142 @@
+// one sprite sliced into frames, drawing a frame doesn't copy any pixels
+
+use crate::{
+    geometry::{Rectangle, Vec2},
+    sprite::{DrawParams, Sprite},
+    Canvas,
+};
+
+pub struct SpriteSheet {
+    pub sprite: Sprite,
+    frames: Vec<Rectangle>,
+}
+
+// uniform cells, in pixels
+#[derive(Clone, Copy, Debug)]
+pub struct Grid {
+    pub cell_width: u32,
+    pub cell_height: u32,
+    // around the whole sheet
+    pub margin: u32,
+    // between cells
+    pub spacing: u32,
+}
+
+impl Grid {
+    pub fn new(cell_width: u32, cell_height: u32) -> Self {
+        Self {
+            cell_width,
+            cell_height,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    // how many whole cells fit across and down
+    pub fn cells(&self, width: u32, height: u32) -> (u32, u32) {
+        let fit = |size: u32, cell: u32| {
+            let inner = size.saturating_sub(2 * self.margin) + self.spacing;
+            inner / (cell + self.spacing).max(1)
+        };
+        (fit(width, self.cell_width), fit(height, self.cell_height))
+    }
+}
+
+impl SpriteSheet {
+    // frames from explicit regions of the sprite
+    pub fn new(sprite: Sprite, frames: Vec<Rectangle>) -> Self {
+        Self { sprite, frames }
+    }
+
+    // frames left to right, then top to bottom
+    pub fn from_grid(sprite: Sprite, grid: Grid) -> Self {
+        let (columns, rows) = grid.cells(sprite.width, sprite.height);
+        let frames = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                Rectangle::new(
+                    (grid.margin + column * (grid.cell_width + grid.spacing)) as f32,
+                    (grid.margin + row * (grid.cell_height + grid.spacing)) as f32,
+                    grid.cell_width as f32,
+                    grid.cell_height as f32,
+                )
+            })
+            .collect();
+
+        Self { sprite, frames }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn frame(&self, index: usize) -> Option<&Rectangle> {
+        self.frames.get(index)
+    }
+
+    pub fn frames(&self) -> &[Rectangle] {
+        &self.frames
+    }
+
+    // out of range frames draw nothing
+    pub fn draw(&self, canvas: &mut Canvas, index: usize, point: &Vec2) {
+        self.draw_with(canvas, index, point, &DrawParams::default());
+    }
+
+    pub fn draw_with(&self, canvas: &mut Canvas, index: usize, point: &Vec2, params: &DrawParams) {
+        if let Some(frame) = self.frame(index) {
+            self.sprite.draw_region(canvas, frame, point, params);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x2 cells with a 1px margin and spacing, each cell filled with its own color
+    fn sheet() -> SpriteSheet {
+        let (width, height) = (8, 5);
+        let mut pixels = vec![0; 4 * width * height];
+        for (i, (left, top)) in [(1, 1), (4, 1)].into_iter().enumerate() {
+            for y in top..top + 2 {
+                for x in left..left + 2 {
+                    let index = 4 * (y * width + x);
+                    pixels[index..index + 4].copy_from_slice(&[i as u8 + 1, 0, 0, 255]);
+                }
+            }
+        }
+
+        let sprite = Sprite::from_rgba(width as u32, height as u32, pixels).unwrap();
+        let grid = Grid {
+            margin: 1,
+            spacing: 1,
+            ..Grid::new(2, 2)
+        };
+        SpriteSheet::from_grid(sprite, grid)
+    }
+
+    #[test]
+    fn sheet_grid() {
+        let sheet = sheet();
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.frame(1), Some(&Rectangle::new(4.0, 1.0, 2.0, 2.0)));
+        assert_eq!(Grid::new(16, 16).cells(40, 33), (2, 2));
+    }
+
+    #[test]
+    fn sheet_draw() {
+        let sheet = sheet();
+        let mut canvas = Canvas::new(3, 3);
+        sheet.draw(&mut canvas, 1, &Vec2::new(1.0, 1.0));
+        sheet.draw(&mut canvas, 7, &Vec2::ZERO);
+
+        assert_eq!(canvas.get_pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(canvas.get_pixel(1, 1), [2, 0, 0, 255]);
+        assert_eq!(canvas.get_pixel(2, 2), [2, 0, 0, 255]);
+    }
+}
@@ -1,4 +1,11 @@
-use crate::{Canvas, geometry::Vec2};
+use std::{fmt, fs, io, path::Path};
+
+use image::{ImageError, RgbaImage};
+
+use crate::{
+    geometry::{Rectangle, Vec2},
+    Canvas,
+};
 
 pub struct Sprite {
     pub width: u32,
@@ -6,33 +13,297 @@ pub struct Sprite {
     pub pixels: Vec<u8>
 }
 
+// how to transform a sprite while drawing it
+#[derive(Clone, Copy, Debug)]
+pub struct DrawParams {
+    // mirrored inside the sprite's own bounds, before anything else
+    pub flip_x: bool,
+    pub flip_y: bool,
+    // radians, clockwise since y points down
+    pub rotation: f32,
+    // sprite pixel to rotate and scale around, stays put on the canvas
+    pub pivot: Vec2,
+    // nearest neighbour, negative values flip too
+    pub scale: Vec2,
+    // multiplied with every pixel
+    pub tint: [u8; 4],
+    pub opacity: f32,
+}
+
+impl Default for DrawParams {
+    fn default() -> Self {
+        Self {
+            flip_x: false,
+            flip_y: false,
+            rotation: 0.0,
+            pivot: Vec2::ZERO,
+            scale: Vec2::ONE,
+            tint: [255, 255, 255, 255],
+            opacity: 1.0,
+        }
+    }
+}
+
+impl DrawParams {
+    // pivot in the middle of a `width` by `height` sprite
+    pub fn centered(width: u32, height: u32) -> Self {
+        Self {
+            pivot: Vec2::new(width as f32, height as f32) / 2.0,
+            ..Self::default()
+        }
+    }
+
+    fn apply(&self, pixel: [u8; 4]) -> [u8; 4] {
+        let mut pixel = pixel;
+        for (channel, tint) in pixel.iter_mut().zip(self.tint) {
+            *channel = (*channel as u32 * tint as u32 / 255) as u8;
+        }
+        pixel[3] = (pixel[3] as f32 * self.opacity.clamp(0.0, 1.0)).round() as u8;
+        pixel
+    }
+}
+
+#[derive(Debug)]
+pub enum SpriteError {
+    // couldn't read the file
+    Io(io::Error),
+    // read it, but it isn't an image we understand
+    Decode(ImageError),
+    // rgba buffer doesn't match width * height
+    Size { width: u32, height: u32, len: usize },
+}
+
 impl Sprite {
+    #[deprecated(note = "use `Sprite::from_path`, which doesn't panic")]
     pub fn new(path: &str) -> Self {
-        let image = image::open(path).expect("no file path x_x").to_rgba8();
+        Self::from_path(path).expect("no file path x_x")
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SpriteError> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
 
-        let mut pixels: Vec<u8> = Vec::new();
-        for pixel in image.pixels() {
-            pixels.append(&mut pixel.0.to_vec());
+    // encoded image bytes, eg. from `include_bytes!("vespa.png")`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SpriteError> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        Ok(Self::from_image(image))
+    }
+
+    // raw rgba pixels, 4 bytes each, row by row
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, SpriteError> {
+        if rgba_len(width, height) != Some(pixels.len()) {
+            return Err(SpriteError::Size {
+                width,
+                height,
+                len: pixels.len(),
+            });
         }
 
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn from_image(image: RgbaImage) -> Self {
         Self {
             width: image.width(),
             height: image.height(),
-            pixels
+            pixels: image.into_raw(),
         }
     }
 
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = 4 * (y * self.width + x) as usize;
+        self.pixels[index..index + 4].try_into().unwrap()
+    }
+
     pub fn draw(&self, canvas: &mut Canvas, point: &Vec2) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index = 4 * (y * self.width + x) as usize;
-                let slice = &self.pixels[index..index + 4];
-                if slice[3] == 0 { continue };
-
-                let pixel: [u8; 4] = slice.try_into().unwrap();
-                canvas.draw_nearest_pixel(point.x + x as f32, point.y + y as f32, pixel);
+        self.draw_with(canvas, point, &DrawParams::default());
+    }
+
+    // `point` is where the sprite's top left would be without any transforms
+    pub fn draw_with(&self, canvas: &mut Canvas, point: &Vec2, params: &DrawParams) {
+        let region = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+        self.draw_region(canvas, &region, point, params);
+    }
+
+    // draws just part of the sprite, as if it were its own sprite
+    // `region` is in sprite pixels and gets clipped to the sprite
+    pub fn draw_region(
+        &self,
+        canvas: &mut Canvas,
+        region: &Rectangle,
+        point: &Vec2,
+        params: &DrawParams,
+    ) {
+        let (left, top, right, bottom) = region.bounds();
+        let (left, top) = (left.round().max(0.0) as u32, top.round().max(0.0) as u32);
+        let right = (right.round().max(0.0) as u32).min(self.width);
+        let bottom = (bottom.round().max(0.0) as u32).min(self.height);
+        if params.scale.x == 0.0 || params.scale.y == 0.0 || left >= right || top >= bottom {
+            return;
+        }
+
+        let size = Vec2::new((right - left) as f32, (bottom - top) as f32);
+        let origin = *point + params.pivot;
+        let rotate = Vec2::from_angle(params.rotation);
+        let unrotate = Vec2::from_angle(-params.rotation);
+
+        // only visit the canvas pixels the transformed sprite can cover
+        let corners = [Vec2::ZERO, Vec2::new(size.x, 0.0), Vec2::new(0.0, size.y), size]
+            .map(|corner| origin + rotate.rotate((corner - params.pivot) * params.scale));
+        let canvas_size = Vec2::new(canvas.width() as f32, canvas.height() as f32);
+        let min = corners.iter().fold(Vec2::splat(f32::INFINITY), |min, corner| min.min(*corner));
+        let max = corners.iter().fold(Vec2::splat(f32::NEG_INFINITY), |max, corner| max.max(*corner));
+        let min = min.floor().max(Vec2::ZERO).as_ivec2();
+        let max = max.ceil().min(canvas_size).as_ivec2();
+
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                // map the canvas pixel's center back into the sprite, so there are no holes
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let local = unrotate.rotate(center - origin) / params.scale + params.pivot;
+                if local.x < 0.0 || local.y < 0.0 || local.x >= size.x || local.y >= size.y {
+                    continue;
+                }
+
+                let (mut u, mut v) = (local.x as u32, local.y as u32);
+                if params.flip_x {
+                    u = size.x as u32 - 1 - u;
+                }
+                if params.flip_y {
+                    v = size.y as u32 - 1 - v;
+                }
+
+                canvas.draw_pixel(x, y, params.apply(self.pixel(left + u, top + v)));
             }
         }
     }
 }
 
+// none when it doesn't fit in memory
+fn rgba_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(4)
+}
+
+impl fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "couldn't read sprite: {}", why),
+            Self::Decode(why) => write!(f, "couldn't decode sprite: {}", why),
+            Self::Size { width, height, len } => write!(
+                f,
+                "a {}x{} sprite needs {} bytes of rgba, found {}",
+                width,
+                height,
+                4 * *width as u128 * *height as u128,
+                len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpriteError {}
+
+impl From<io::Error> for SpriteError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ImageError> for SpriteError {
+    fn from(value: ImageError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    // red column on the left, blue on the right
+    fn sprite() -> Sprite {
+        let pixels = [RED, BLUE, RED, BLUE, RED, BLUE].concat();
+        Sprite::from_rgba(2, 3, pixels).unwrap()
+    }
+
+    #[test]
+    fn sprite_draw() {
+        let mut canvas = Canvas::new(4, 4);
+        sprite().draw(&mut canvas, &Vec2::new(1.4, 0.6));
+        assert_eq!(canvas.get_pixel(1, 1), RED);
+        assert_eq!(canvas.get_pixel(2, 3), BLUE);
+        assert_eq!(canvas.get_pixel(1, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sprite_draw_flipped_and_scaled() {
+        let mut canvas = Canvas::new(4, 6);
+        let params = DrawParams {
+            flip_x: true,
+            scale: Vec2::splat(2.0),
+            ..DrawParams::default()
+        };
+        sprite().draw_with(&mut canvas, &Vec2::ZERO, &params);
+
+        assert_eq!(canvas.get_pixel(1, 5), BLUE);
+        assert_eq!(canvas.get_pixel(2, 0), RED);
+        assert_eq!(canvas.get_pixel(3, 3), RED);
+    }
+
+    #[test]
+    fn sprite_draw_rotated() {
+        let mut canvas = Canvas::new(4, 4);
+        let params = DrawParams {
+            rotation: std::f32::consts::FRAC_PI_2,
+            opacity: 0.5,
+            ..DrawParams::centered(2, 3)
+        };
+        // a quarter turn clockwise puts the red column on top
+        sprite().draw_with(&mut canvas, &Vec2::new(1.0, 0.5), &params);
+
+        assert_eq!(canvas.get_pixel(1, 1), [255, 0, 0, 128]);
+        assert_eq!(canvas.get_pixel(2, 2), [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn sprite_from_bytes() {
+        let sprite = Sprite::from_bytes(include_bytes!("../assets/vespa.png")).unwrap();
+        assert_eq!((sprite.width, sprite.height), (42, 38));
+        assert_eq!(sprite.pixels.len(), 4 * 42 * 38);
+    }
+
+    #[test]
+    fn sprite_errors() {
+        assert!(matches!(
+            Sprite::from_path("./assets/nothing_here.png"),
+            Err(SpriteError::Io(_))
+        ));
+        assert!(matches!(
+            Sprite::from_bytes(b"not a png"),
+            Err(SpriteError::Decode(_))
+        ));
+        assert!(matches!(
+            Sprite::from_rgba(2, 2, vec![0; 12]),
+            Err(SpriteError::Size { len: 12, .. })
+        ));
+        assert!(Sprite::from_rgba(2, 2, vec![0; 16]).is_ok());
+
+        // too big to ever match, rather than overflowing
+        let error = Sprite::from_rgba(u32::MAX, u32::MAX, Vec::new())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "a 4294967295x4294967295 sprite needs 73786976260478468100 bytes of rgba, found 0"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+// states like menu, playing and paused, with systems that run when they're entered and left
+
+use bevy_ecs::{
+    schedule::{apply_state_transition, run_enter_schedule, Schedule, Schedules},
+    system::Resource,
+    world::World,
+};
+
+pub use bevy_ecs::schedule::{
+    common_conditions::{in_state, state_changed},
+    NextState, OnEnter, OnExit, State, States,
+};
+
+// what to run for each kind of state, once a frame
+#[derive(Resource, Default)]
+struct StateUpdates {
+    enter: Vec<fn(&mut World)>,
+    transition: Vec<fn(&mut World)>,
+}
+
+// starts in `S::default()`, with an empty enter and exit schedule for every variant
+pub(crate) fn add_state<S: States>(world: &mut World) {
+    if world.contains_resource::<State<S>>() {
+        return;
+    }
+    world.init_resource::<State<S>>();
+    world.init_resource::<NextState<S>>();
+
+    let mut schedules = world.resource_mut::<Schedules>();
+    for state in S::variants() {
+        schedules.insert(OnEnter(state.clone()), Schedule::new());
+        schedules.insert(OnExit(state), Schedule::new());
+    }
+
+    let mut updates = world.get_resource_or_insert_with(StateUpdates::default);
+    updates.enter.push(run_enter_schedule::<S>);
+    updates.transition.push(apply_state_transition::<S>);
+}
+
+// the first state's enter systems, right after `Stage::Startup`
+pub(crate) fn enter_states(world: &mut World) {
+    if let Some(updates) = world.get_resource::<StateUpdates>() {
+        for enter in updates.enter.clone() {
+            enter(world);
+        }
+    }
+}
+
+// `NextState` takes over before `Stage::FixedUpdate`, running the old state's exit systems
+// then the new one's enter systems
+pub(crate) fn apply_transitions(world: &mut World) {
+    if let Some(updates) = world.get_resource::<StateUpdates>() {
+        for transition in updates.transition.clone() {
+            transition(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::IntoSystemConfig,
+        system::{Res, ResMut},
+    };
+
+    use super::*;
+    use crate::{Duration, Input, Key, Panda, PandaOptions};
+
+    #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    enum Game {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    #[derive(Resource, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn start(input: Res<Input>, mut next: ResMut<NextState<Game>>) {
+        if input.key_pressed(Key::Return) {
+            next.set(Game::Playing);
+        }
+    }
+
+    fn state_panda() -> Panda {
+        Panda::builder(PandaOptions::default())
+            .init_resource::<Log>()
+            .add_state::<Game>()
+            .add_enter_system(Game::Menu, |mut log: ResMut<Log>| log.0.push("enter menu"))
+            .add_exit_system(Game::Menu, |mut log: ResMut<Log>| log.0.push("exit menu"))
+            .add_enter_system(Game::Playing, |mut log: ResMut<Log>| {
+                log.0.push("enter playing")
+            })
+            .add_system(start.run_if(in_state(Game::Menu)))
+            .add_system(
+                (|mut log: ResMut<Log>| log.0.push("playing")).run_if(in_state(Game::Playing)),
+            )
+            .build_headless()
+    }
+
+    #[test]
+    fn state_transitions() {
+        let mut panda = state_panda();
+        panda.update(Duration::ZERO);
+        assert_eq!(panda.world.resource::<Log>().0, ["enter menu"]);
+
+        // set during this frame, takes over at the start of the next
+        panda.press_key(Key::Return);
+        panda.update(Duration::ZERO);
+        assert_eq!(panda.world.resource::<State<Game>>().0, Game::Menu);
+        panda.update(Duration::ZERO);
+        assert_eq!(panda.world.resource::<State<Game>>().0, Game::Playing);
+        assert_eq!(
+            panda.world.resource::<Log>().0,
+            ["enter menu", "exit menu", "enter playing", "playing"]
+        );
+    }
+
+    #[test]
+    fn state_set_before_first_frame() {
+        let mut panda = state_panda();
+        panda
+            .world
+            .resource_mut::<NextState<Game>>()
+            .set(Game::Playing);
+        panda.update(Duration::ZERO);
+        assert_eq!(
+            panda.world.resource::<Log>().0,
+            ["enter menu", "exit menu", "enter playing", "playing"]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// bitmap fonts, a pixel is either ink or not!
+
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+use image::{ImageResult, RgbaImage};
+
+use crate::Canvas;
+
+mod bdf;
+
+pub use bdf::BdfError;
+
+pub struct Glyph {
+    pub width: u32,
+    pub height: u32,
+    // from the pen position (top left of the line) to the glyph's top left
+    pub offset: (i32, i32),
+    // how far the pen moves after drawing
+    pub advance: i32,
+    ink: Vec<bool>,
+}
+
+impl Glyph {
+    pub fn new(width: u32, height: u32, offset: (i32, i32), advance: i32, ink: Vec<bool>) -> Self {
+        assert_eq!(
+            Some(ink.len()),
+            (width as usize).checked_mul(height as usize),
+            "glyph ink doesn't match its size"
+        );
+        Self {
+            width,
+            height,
+            offset,
+            advance,
+            ink,
+        }
+    }
+
+    pub fn ink(&self, x: u32, y: u32) -> bool {
+        self.ink[(y * self.width + x) as usize]
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, x: i32, y: i32, pixel: [u8; 4]) {
+        for gy in 0..self.height {
+            for gx in 0..self.width {
+                if self.ink(gx, gy) {
+                    let (dx, dy) = self.offset;
+                    canvas.draw_pixel(x + dx + gx as i32, y + dy + gy as i32, pixel);
+                }
+            }
+        }
+    }
+}
+
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    line_height: u32,
+    // drawn for chars the font doesn't have
+    replacement: Option<char>,
+}
+
+impl Font {
+    pub fn new(line_height: u32) -> Self {
+        Self {
+            glyphs: HashMap::new(),
+            line_height,
+            replacement: None,
+        }
+    }
+
+    // the built in 5x8 pixel font, covers printable ascii
+    pub fn builtin() -> &'static Font {
+        static BUILTIN: OnceLock<Font> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut font = Font::new(8);
+            for (i, rows) in FONT_5X8.iter().enumerate() {
+                let ink = (0..8 * 5)
+                    .map(|j| rows[j / 5] & (0x80 >> (j % 5)) != 0)
+                    .collect();
+                let char = char::from(b' ' + i as u8);
+                font.insert(char, Glyph::new(5, 8, (0, 0), 5, ink));
+            }
+            font.set_replacement(Some('?'));
+            font
+        })
+    }
+
+    // glyphs laid out on a grid, left to right then top to bottom, starting at `first`
+    // opaque bright pixels are ink, dark or transparent pixels aren't
+    pub fn from_sheet(
+        path: impl AsRef<Path>,
+        glyph_width: u32,
+        glyph_height: u32,
+        first: char,
+    ) -> ImageResult<Self> {
+        let image = image::open(path)?.to_rgba8();
+        Ok(Self::from_image(&image, glyph_width, glyph_height, first))
+    }
+
+    pub fn from_image(image: &RgbaImage, glyph_width: u32, glyph_height: u32, first: char) -> Self {
+        let mut font = Font::new(glyph_height);
+        let columns = image.width() / glyph_width;
+        let rows = image.height() / glyph_height;
+
+        for i in 0..columns * rows {
+            let Some(char) = char::from_u32(first as u32 + i) else { continue };
+            let (left, top) = ((i % columns) * glyph_width, (i / columns) * glyph_height);
+
+            let mut ink = Vec::with_capacity((glyph_width * glyph_height) as usize);
+            for y in top..top + glyph_height {
+                for x in left..left + glyph_width {
+                    let [r, g, b, a] = image.get_pixel(x, y).0;
+                    ink.push(a >= 128 && r.max(g).max(b) >= 128);
+                }
+            }
+
+            let glyph = Glyph::new(glyph_width, glyph_height, (0, 0), glyph_width as i32, ink);
+            font.insert(char, glyph);
+        }
+
+        font
+    }
+
+    pub fn insert(&mut self, char: char, glyph: Glyph) {
+        self.glyphs.insert(char, glyph);
+    }
+
+    pub fn set_replacement(&mut self, replacement: Option<char>) {
+        self.replacement = replacement;
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    // falls back to the replacement glyph
+    pub fn glyph(&self, char: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&char)
+            .or_else(|| self.glyphs.get(&self.replacement?))
+    }
+
+    // width and height of the text in pixels
+    pub fn measure(&self, text: &str) -> (u32, u32) {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let advance: i32 = line.chars().filter_map(|c| self.glyph(c)).map(|g| g.advance).sum();
+            width = width.max(advance.max(0) as u32);
+            lines += 1;
+        }
+
+        (width, lines * self.line_height)
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, x: i32, y: i32, text: &str, pixel: [u8; 4]) {
+        for (i, line) in text.split('\n').enumerate() {
+            let mut pen = x;
+            let top = y + (i as u32 * self.line_height) as i32;
+            for glyph in line.chars().filter_map(|c| self.glyph(c)) {
+                glyph.draw(canvas, pen, top, pixel);
+                pen += glyph.advance;
+            }
+        }
+    }
+}
+
+impl Canvas {
+    // draws with the built in font
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, pixel: [u8; 4]) {
+        Font::builtin().draw(self, x, y, text, pixel);
+    }
+}
+
+// 5x8 font from the X11 misc-fixed collection (public domain), one byte per row
+const FONT_5X8: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x20, 0x20, 0x20, 0x20, 0x00, 0x20, 0x00], // '!'
+    [0x00, 0x50, 0x50, 0x50, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x50, 0x50, 0xf8, 0x50, 0xf8, 0x50, 0x50, 0x00], // '#'
+    [0x20, 0x70, 0xa0, 0x70, 0x28, 0x70, 0x20, 0x00], // '$'
+    [0x00, 0x40, 0x50, 0x20, 0x50, 0x10, 0x00, 0x00], // '%'
+    [0x40, 0xa0, 0xa0, 0x40, 0xa0, 0xa0, 0x50, 0x00], // '&'
+    [0x00, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x00, 0x20, 0x40, 0x40, 0x40, 0x40, 0x20, 0x00], // '('
+    [0x00, 0x40, 0x20, 0x20, 0x20, 0x20, 0x40, 0x00], // ')'
+    [0x00, 0x00, 0x90, 0x60, 0xf0, 0x60, 0x90, 0x00], // '*'
+    [0x00, 0x00, 0x20, 0x20, 0xf8, 0x20, 0x20, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x20, 0x40], // ','
+    [0x00, 0x00, 0x00, 0x00, 0xf0, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x70, 0x20], // '.'
+    [0x00, 0x10, 0x10, 0x20, 0x40, 0x80, 0x80, 0x00], // '/'
+    [0x00, 0x20, 0x50, 0x50, 0x50, 0x50, 0x20, 0x00], // '0'
+    [0x00, 0x20, 0x60, 0x20, 0x20, 0x20, 0x70, 0x00], // '1'
+    [0x00, 0x60, 0x90, 0x10, 0x60, 0x80, 0xf0, 0x00], // '2'
+    [0x00, 0xf0, 0x20, 0x60, 0x10, 0x90, 0x60, 0x00], // '3'
+    [0x00, 0x20, 0x60, 0xa0, 0xf0, 0x20, 0x20, 0x00], // '4'
+    [0x00, 0xf0, 0x80, 0xe0, 0x10, 0x90, 0x60, 0x00], // '5'
+    [0x00, 0x60, 0x80, 0xe0, 0x90, 0x90, 0x60, 0x00], // '6'
+    [0x00, 0xf0, 0x10, 0x20, 0x20, 0x40, 0x40, 0x00], // '7'
+    [0x00, 0x60, 0x90, 0x60, 0x90, 0x90, 0x60, 0x00], // '8'
+    [0x00, 0x60, 0x90, 0x90, 0x70, 0x10, 0x60, 0x00], // '9'
+    [0x00, 0x00, 0x60, 0x60, 0x00, 0x60, 0x60, 0x00], // ':'
+    [0x00, 0x00, 0x30, 0x30, 0x00, 0x30, 0x20, 0x40], // ';'
+    [0x00, 0x10, 0x20, 0x40, 0x40, 0x20, 0x10, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0xf0, 0x00, 0xf0, 0x00, 0x00], // '='
+    [0x00, 0x40, 0x20, 0x10, 0x10, 0x20, 0x40, 0x00], // '>'
+    [0x00, 0x20, 0x50, 0x10, 0x20, 0x00, 0x20, 0x00], // '?'
+    [0x30, 0x48, 0x98, 0xa8, 0xa8, 0x90, 0x40, 0x30], // '@'
+    [0x00, 0x60, 0x90, 0x90, 0xf0, 0x90, 0x90, 0x00], // 'A'
+    [0x00, 0xe0, 0x90, 0xe0, 0x90, 0x90, 0xe0, 0x00], // 'B'
+    [0x00, 0x60, 0x90, 0x80, 0x80, 0x90, 0x60, 0x00], // 'C'
+    [0x00, 0xe0, 0x90, 0x90, 0x90, 0x90, 0xe0, 0x00], // 'D'
+    [0x00, 0xf0, 0x80, 0xe0, 0x80, 0x80, 0xf0, 0x00], // 'E'
+    [0x00, 0xf0, 0x80, 0xe0, 0x80, 0x80, 0x80, 0x00], // 'F'
+    [0x00, 0x60, 0x90, 0x80, 0xb0, 0x90, 0x60, 0x00], // 'G'
+    [0x00, 0x90, 0x90, 0xf0, 0x90, 0x90, 0x90, 0x00], // 'H'
+    [0x00, 0x70, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00], // 'I'
+    [0x00, 0x70, 0x20, 0x20, 0x20, 0xa0, 0x40, 0x00], // 'J'
+    [0x00, 0x90, 0xa0, 0xc0, 0xa0, 0xa0, 0x90, 0x00], // 'K'
+    [0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0xf0, 0x00], // 'L'
+    [0x00, 0x90, 0xf0, 0xf0, 0x90, 0x90, 0x90, 0x00], // 'M'
+    [0x00, 0x90, 0xd0, 0xf0, 0xb0, 0xb0, 0x90, 0x00], // 'N'
+    [0x00, 0x60, 0x90, 0x90, 0x90, 0x90, 0x60, 0x00], // 'O'
+    [0x00, 0xe0, 0x90, 0x90, 0xe0, 0x80, 0x80, 0x00], // 'P'
+    [0x00, 0x60, 0x90, 0x90, 0xd0, 0xb0, 0x60, 0x10], // 'Q'
+    [0x00, 0xe0, 0x90, 0x90, 0xe0, 0x90, 0x90, 0x00], // 'R'
+    [0x00, 0x60, 0x90, 0x40, 0x20, 0x90, 0x60, 0x00], // 'S'
+    [0x00, 0x70, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00], // 'T'
+    [0x00, 0x90, 0x90, 0x90, 0x90, 0x90, 0x60, 0x00], // 'U'
+    [0x00, 0x90, 0x90, 0x90, 0x90, 0x60, 0x60, 0x00], // 'V'
+    [0x00, 0x90, 0x90, 0x90, 0xf0, 0xf0, 0x90, 0x00], // 'W'
+    [0x00, 0x90, 0x90, 0x60, 0x60, 0x90, 0x90, 0x00], // 'X'
+    [0x00, 0x88, 0x88, 0x50, 0x20, 0x20, 0x20, 0x00], // 'Y'
+    [0x00, 0xf0, 0x10, 0x20, 0x40, 0x80, 0xf0, 0x00], // 'Z'
+    [0x00, 0x70, 0x40, 0x40, 0x40, 0x40, 0x70, 0x00], // '['
+    [0x00, 0x80, 0x80, 0x40, 0x20, 0x10, 0x10, 0x00], // '\\'
+    [0x00, 0x70, 0x10, 0x10, 0x10, 0x10, 0x70, 0x00], // ']'
+    [0x00, 0x20, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0], // '_'
+    [0x00, 0x40, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x70, 0x90, 0x90, 0x70, 0x00], // 'a'
+    [0x00, 0x80, 0x80, 0xe0, 0x90, 0x90, 0xe0, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x30, 0x40, 0x40, 0x30, 0x00], // 'c'
+    [0x00, 0x10, 0x10, 0x70, 0x90, 0x90, 0x70, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x60, 0xb0, 0xc0, 0x60, 0x00], // 'e'
+    [0x00, 0x20, 0x50, 0x40, 0xe0, 0x40, 0x40, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x60, 0x90, 0x70, 0x10, 0x60], // 'g'
+    [0x00, 0x80, 0x80, 0xe0, 0x90, 0x90, 0x90, 0x00], // 'h'
+    [0x00, 0x20, 0x00, 0x60, 0x20, 0x20, 0x70, 0x00], // 'i'
+    [0x00, 0x10, 0x00, 0x10, 0x10, 0x10, 0x50, 0x20], // 'j'
+    [0x00, 0x80, 0x80, 0x90, 0xe0, 0x90, 0x90, 0x00], // 'k'
+    [0x00, 0x60, 0x20, 0x20, 0x20, 0x20, 0x70, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0xd0, 0xa8, 0xa8, 0xa8, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0xe0, 0x90, 0x90, 0x90, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x60, 0x90, 0x90, 0x60, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0xe0, 0x90, 0xe0, 0x80, 0x80], // 'p'
+    [0x00, 0x00, 0x00, 0x70, 0x90, 0x70, 0x10, 0x10], // 'q'
+    [0x00, 0x00, 0x00, 0xa0, 0xd0, 0x80, 0x80, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x30, 0x60, 0x10, 0x60, 0x00], // 's'
+    [0x00, 0x40, 0x40, 0xe0, 0x40, 0x50, 0x20, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x90, 0x90, 0x90, 0x70, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x50, 0x50, 0x50, 0x20, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x88, 0xa8, 0xa8, 0x50, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x90, 0x60, 0x60, 0x90, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x90, 0x90, 0x70, 0x90, 0x60], // 'y'
+    [0x00, 0x00, 0x00, 0xf0, 0x20, 0x40, 0xf0, 0x00], // 'z'
+    [0x30, 0x40, 0x20, 0xc0, 0x20, 0x40, 0x30, 0x00], // '{'
+    [0x00, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00], // '|'
+    [0xc0, 0x20, 0x40, 0x30, 0x40, 0x20, 0xc0, 0x00], // '}'
+    [0x00, 0x50, 0xa0, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{canvas::Color, golden::assert_golden};
+
+    #[test]
+    fn font_measure() {
+        let font = Font::builtin();
+        assert_eq!(font.measure("panda!"), (30, 8));
+        assert_eq!(font.measure("hi\npanda"), (25, 16));
+        assert_eq!(font.measure(""), (0, 8));
+        assert_eq!(font.measure("\u{1F43C}"), (5, 8));
+    }
+
+    #[test]
+    fn font_from_image() {
+        // two 2x2 glyphs: a full block and a diagonal
+        let mut image = RgbaImage::new(4, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (3, 1)] {
+            image.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+        }
+
+        let font = Font::from_image(&image, 2, 2, 'a');
+        assert_eq!(font.measure("ab"), (4, 2));
+        assert!(font.glyph('c').is_none());
+
+        let b = font.glyph('b').unwrap();
+        assert!(b.ink(0, 0) && b.ink(1, 1));
+        assert!(!b.ink(1, 0) && !b.ink(0, 1));
+    }
+
+    #[test]
+    fn golden_text() {
+        let mut canvas = Canvas::new(64, 20);
+        canvas.clear(Color::BLACK.pixel());
+        canvas.draw_text(2, 2, "panda!\nscore: 42", Color::WHITE.pixel());
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/text.png");
+        assert_golden(&canvas, path, 0);
+    }
+}
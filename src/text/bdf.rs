@@ -0,0 +1,312 @@
+// parser for the plain text BDF bitmap font format
+// https://www.adobe.com/content/dam/acom/en/devnet/font/pdfs/5005.BDF_Spec.pdf
+
+use std::{fmt, fs, io, path::Path};
+
+use super::{Font, Glyph};
+
+// way past any real bitmap font, but a bad BBX can't ask for gigabytes of ink
+const MAX_GLYPH_PIXELS: usize = 1 << 20;
+
+#[derive(Debug)]
+pub enum BdfError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    // the file ran out before the font was complete
+    UnexpectedEnd(&'static str),
+}
+
+impl Font {
+    pub fn from_bdf(path: impl AsRef<Path>) -> Result<Self, BdfError> {
+        Self::parse_bdf(&fs::read_to_string(path)?)
+    }
+
+    // encodings are read as unicode codepoints
+    pub fn parse_bdf(source: &str) -> Result<Self, BdfError> {
+        let mut parser = Parser::default();
+        for (i, line) in source.lines().enumerate() {
+            parser.line(line).map_err(|message| BdfError::Parse {
+                line: i + 1,
+                message,
+            })?;
+        }
+        parser.finish()
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    bounding_box: Option<[i32; 4]>,
+    ascent: Option<i32>,
+    descent: Option<i32>,
+    default_char: Option<u32>,
+    // font wide DWIDTH, glyphs can override it
+    advance: Option<i32>,
+    glyphs: Vec<(Option<char>, Glyph)>,
+    current: Option<Char>,
+    in_bitmap: bool,
+}
+
+#[derive(Default)]
+struct Char {
+    char: Option<char>,
+    advance: Option<i32>,
+    bbx: Option<[i32; 4]>,
+    rows: Vec<Vec<u8>>,
+}
+
+impl Parser {
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        if self.in_bitmap {
+            if keyword == "ENDCHAR" {
+                self.in_bitmap = false;
+                return self.end_char();
+            }
+            let row = hex_row(line)?;
+            self.current.as_mut().ok_or("bitmap outside of a char")?.rows.push(row);
+            return Ok(());
+        }
+
+        match keyword {
+            "FONTBOUNDINGBOX" => self.bounding_box = Some(numbers(rest)?),
+            "FONT_ASCENT" => self.ascent = Some(number(rest)?),
+            "FONT_DESCENT" => self.descent = Some(number(rest)?),
+            "DEFAULT_CHAR" => self.default_char = Some(number(rest)? as u32),
+            "STARTCHAR" => self.current = Some(Char::default()),
+            "ENCODING" => {
+                let encoding: i32 = number(rest.split_whitespace().next().unwrap_or(""))?;
+                let char = u32::try_from(encoding).ok().and_then(char::from_u32);
+                self.char()?.char = char;
+            }
+            "DWIDTH" => {
+                let [x, _] = numbers(rest)?;
+                match self.current.as_mut() {
+                    Some(char) => char.advance = Some(x),
+                    None => self.advance = Some(x),
+                }
+            }
+            "BBX" => self.char()?.bbx = Some(numbers(rest)?),
+            "BITMAP" => {
+                self.char()?;
+                self.in_bitmap = true;
+            }
+            "ENDCHAR" => return self.end_char(),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn char(&mut self) -> Result<&mut Char, String> {
+        self.current.as_mut().ok_or_else(|| "expected STARTCHAR first".to_string())
+    }
+
+    fn end_char(&mut self) -> Result<(), String> {
+        let char = self.current.take().ok_or("ENDCHAR without STARTCHAR")?;
+        let [width, height, x, y] = char
+            .bbx
+            .or(self.bounding_box)
+            .ok_or("char without a BBX")?;
+        if width < 0 || height < 0 {
+            return Err("negative glyph size".to_string());
+        }
+        if char.rows.len() < height as usize {
+            return Err(format!("expected {} bitmap rows", height));
+        }
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&size| size <= MAX_GLYPH_PIXELS)
+            .ok_or_else(|| format!("{}x{} glyph is too big", width, height))?;
+        let top = y.checked_add(height).ok_or("glyph is too far from the baseline")?;
+
+        let ink = (0..size)
+            .map(|i| {
+                let (row, column) = (i / width as usize, i % width as usize);
+                let byte = char.rows[row].get(column / 8).copied().unwrap_or(0);
+                byte & (0x80 >> (column % 8)) != 0
+            })
+            .collect();
+
+        // bbx offsets are from the baseline with y going up, keep them for `finish`
+        let advance = char.advance.or(self.advance).unwrap_or(width);
+        let glyph = Glyph::new(width as u32, height as u32, (x, top), advance, ink);
+        self.glyphs.push((char.char, glyph));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Font, BdfError> {
+        if self.current.is_some() {
+            return Err(BdfError::UnexpectedEnd("STARTCHAR without ENDCHAR"));
+        }
+        let (ascent, descent) = match (self.ascent, self.descent, self.bounding_box) {
+            (Some(ascent), Some(descent), _) => (ascent, descent),
+            (_, _, Some([_, height, _, y])) => (height + y, -y),
+            _ => {
+                return Err(BdfError::UnexpectedEnd(
+                    "no FONT_ASCENT, FONT_DESCENT or FONTBOUNDINGBOX",
+                ))
+            }
+        };
+
+        let mut font = Font::new((ascent + descent).max(0) as u32);
+        for (char, mut glyph) in self.glyphs {
+            let Some(char) = char else { continue };
+            // move the glyph's top from above the baseline to below the line's top
+            glyph.offset.1 = ascent - glyph.offset.1;
+            font.insert(char, glyph);
+        }
+
+        let replacement = self
+            .default_char
+            .and_then(char::from_u32)
+            .into_iter()
+            .chain(['\u{FFFD}', '?'])
+            .find(|char| font.glyphs.contains_key(char));
+        font.set_replacement(replacement);
+
+        Ok(font)
+    }
+}
+
+fn number(text: &str) -> Result<i32, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("expected a number, found `{}`", text))
+}
+
+fn numbers<const N: usize>(text: &str) -> Result<[i32; N], String> {
+    let numbers = text
+        .split_whitespace()
+        .map(number)
+        .collect::<Result<Vec<_>, _>>()?;
+    numbers
+        .try_into()
+        .map_err(|_| format!("expected {} numbers, found `{}`", N, text))
+}
+
+fn hex_row(text: &str) -> Result<Vec<u8>, String> {
+    let bad = || format!("bad bitmap row `{}`", text);
+    // an odd digit left over means a broken row
+    let pairs = text.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(bad());
+    }
+
+    pairs
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect::<Option<_>>()
+        .ok_or_else(bad)
+}
+
+impl fmt::Display for BdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "{}", why),
+            Self::Parse { line, message } => write!(f, "bdf line {}: {}", line, message),
+            Self::UnexpectedEnd(message) => write!(f, "bdf ended early: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for BdfError {}
+
+impl From<io::Error> for BdfError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "\
+STARTFONT 2.1
+FONT -test-small
+SIZE 6 75 75
+FONTBOUNDINGBOX 4 6 0 -2
+STARTPROPERTIES 2
+FONT_ASCENT 4
+FONT_DESCENT 2
+ENDPROPERTIES
+CHARS 3
+STARTCHAR question
+ENCODING 63
+DWIDTH 4 0
+BBX 3 4 0 0
+BITMAP
+E0
+20
+00
+40
+ENDCHAR
+STARTCHAR g
+ENCODING 103
+DWIDTH 4 0
+BBX 3 4 0 -2
+BITMAP
+E0
+A0
+60
+C0
+ENDCHAR
+STARTCHAR arrow
+ENCODING 8594
+DWIDTH 6 0
+BBX 5 1 0 1
+BITMAP
+F8
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn bdf_parse() {
+        let font = Font::parse_bdf(FONT).unwrap();
+        assert_eq!(font.line_height(), 6);
+
+        let g = font.glyph('g').unwrap();
+        assert_eq!((g.width, g.height, g.offset, g.advance), (3, 4, (0, 2), 4));
+        assert!(g.ink(0, 0) && g.ink(2, 1) && !g.ink(1, 1));
+
+        let arrow = font.glyph('→').unwrap();
+        assert_eq!((arrow.offset, arrow.advance), ((0, 2), 6));
+        assert!((0..5).all(|x| arrow.ink(x, 0)));
+
+        assert_eq!(font.measure("g→x"), (14, 6));
+        assert_eq!(font.glyph('x').unwrap().advance, 4);
+    }
+
+    #[test]
+    fn bdf_errors() {
+        let broken = FONT.replace("A0", "A");
+        match Font::parse_bdf(&broken) {
+            Err(BdfError::Parse { line, .. }) => assert_eq!(line, 26),
+            _ => panic!("expected a parse error"),
+        }
+
+        // cut off in the middle of a char
+        let cut = &FONT[..FONT.find("F8").unwrap()];
+        assert!(matches!(
+            Font::parse_bdf(cut),
+            Err(BdfError::UnexpectedEnd(_))
+        ));
+        assert!(matches!(
+            Font::parse_bdf("STARTFONT 2.1\n"),
+            Err(BdfError::UnexpectedEnd(_))
+        ));
+
+        // a glyph far too wide to be real
+        let wide = FONT.replace("BBX 3 4 0 0", "BBX 2147483647 4 0 0");
+        match Font::parse_bdf(&wide) {
+            Err(BdfError::Parse { line, .. }) => assert_eq!(line, 19),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
@@ -0,0 +1,658 @@
+// loader for maps made in the Tiled editor, both .tmx (xml) and .tmj (json)
+// https://doc.mapeditor.org/en/stable/reference/tmx-map-format/
+
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use bevy_ecs::{
+    entity::Entity,
+    prelude::Component,
+    world::{EntityMut, World},
+};
+use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+
+use crate::{
+    geometry::{Circle, Polygon, Rectangle, Vec2},
+    sheet::SpriteSheet,
+    sprite::{Sprite, SpriteError},
+    tilemap::{Tile, Tilemap},
+};
+
+mod json;
+mod xml;
+
+pub struct TiledMap {
+    // in tiles
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<Tileset>,
+    // groups are flattened, drawn first to last
+    pub layers: Vec<Layer>,
+    pub properties: Properties,
+}
+
+pub struct Tileset {
+    pub first_gid: u32,
+    pub name: String,
+    // relative paths are resolved when loading
+    pub image: PathBuf,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    // by local tile id
+    pub tiles: HashMap<u32, Properties>,
+}
+
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    pub kind: LayerKind,
+}
+
+pub enum LayerKind {
+    // finite maps have a single chunk
+    Tiles(Vec<Chunk>),
+    Objects(Vec<Object>),
+}
+
+// a block of tile gids, 0 means no tile
+pub struct Chunk {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub gids: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub visible: bool,
+    // degrees clockwise, the shapes themselves stay axis aligned
+    pub rotation: f32,
+    // tile objects
+    pub gid: Option<u32>,
+    pub shape: Shape,
+    pub properties: Properties,
+}
+
+// in map pixels, with the layer offset applied
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rectangle(Rectangle),
+    Ellipse(Rectangle),
+    Point(Vec2),
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    // colors, files and everything else come through as text
+    String(String),
+}
+
+pub type Properties = HashMap<String, Property>;
+
+// on every entity spawned from an object, next to its shape
+#[derive(Component, Clone, Debug)]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub layer: String,
+    // false when the object or its layer is hidden
+    pub visible: bool,
+    pub rotation: f32,
+    pub gid: Option<u32>,
+    pub properties: Properties,
+}
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Xml(roxmltree::Error),
+    Sprite(SpriteError),
+    // parsed, but not a map we understand
+    Invalid(String),
+}
+
+// the high bits of a gid flip the tile
+const FLIP_X: u32 = 0x8000_0000;
+const FLIP_Y: u32 = 0x4000_0000;
+const GID_MASK: u32 = 0x0FFF_FFFF;
+
+// a stacked tileset sheet gets a frame for every gid up to the last tile, and all the
+// images copied into it, so maps asking for more than this are turned away
+const MAX_TILES: u32 = 1 << 20;
+const MAX_SHEET_PIXELS: usize = 1 << 26;
+
+impl TiledMap {
+    // picks the format from the extension, .tmx is xml and anything else json
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, TiledError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        if path.extension().is_some_and(|extension| extension == "tmx") {
+            Self::from_xml(&source, base)
+        } else {
+            Self::from_json(&source, base)
+        }
+    }
+
+    // external tilesets and images are found relative to `base`
+    pub fn from_json(source: &str, base: impl AsRef<Path>) -> Result<Self, TiledError> {
+        json::map(source, base.as_ref())
+    }
+
+    pub fn from_xml(source: &str, base: impl AsRef<Path>) -> Result<Self, TiledError> {
+        xml::map(source, base.as_ref())
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    // loads the tileset images and fills in every tile layer,
+    // tiles with a `solid` property set are solid
+    pub fn tilemap(&self) -> Result<Tilemap, TiledError> {
+        let images = self
+            .tilesets
+            .iter()
+            .map(|tileset| Sprite::from_path(&tileset.image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut tilemap = Tilemap::new(self.tileset(&images)?, self.tile_width, self.tile_height);
+
+        for layer in &self.layers {
+            let LayerKind::Tiles(chunks) = &layer.kind else {
+                continue;
+            };
+
+            let tile_layer = tilemap.add_layer(&layer.name);
+            tile_layer.visible = layer.visible;
+            for chunk in chunks {
+                for (i, &gid) in chunk.gids.iter().enumerate() {
+                    let Some(tile) = self.tile(gid) else {
+                        continue;
+                    };
+                    let x = chunk.x + (i as u32 % chunk.width.max(1)) as i32;
+                    let y = chunk.y + (i as u32 / chunk.width.max(1)) as i32;
+                    tile_layer.set(x, y, tile);
+                }
+            }
+        }
+
+        Ok(tilemap)
+    }
+
+    // every tileset stacked into one sheet, where frame `gid - 1` is that gid's tile
+    fn tileset(&self, images: &[Sprite]) -> Result<SpriteSheet, TiledError> {
+        let too_big = || invalid("tileset images are too big to stack");
+        let width = images.iter().map(|image| image.width).max().unwrap_or(0);
+        let height = images
+            .iter()
+            .try_fold(0u32, |height, image| height.checked_add(image.height))
+            .ok_or_else(too_big)?;
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&size| size <= MAX_SHEET_PIXELS)
+            .ok_or_else(too_big)?;
+        let mut pixels = vec![0; 4 * size];
+
+        let frame_count = self
+            .tilesets
+            .iter()
+            .try_fold(0, |count: u32, tileset| {
+                let end = tileset.first_gid.checked_add(tileset.tile_count)?;
+                Some(count.max(end - 1)).filter(|&count| count <= MAX_TILES)
+            })
+            .ok_or_else(|| invalid(format!("tile gids can't go past {}", MAX_TILES)))?;
+        let mut frames = vec![Rectangle::new(0.0, 0.0, 0.0, 0.0); frame_count as usize];
+
+        let mut top = 0;
+        for (tileset, image) in self.tilesets.iter().zip(images) {
+            let row = 4 * image.width as usize;
+            for y in 0..image.height as usize {
+                let start = 4 * (top as usize + y) * width as usize;
+                pixels[start..start + row].copy_from_slice(&image.pixels[y * row..(y + 1) * row]);
+            }
+
+            // in floats, so odd margins and spacings can't overflow
+            let (margin, spacing) = (tileset.margin as f32, tileset.spacing as f32);
+            let (tile_width, tile_height) = (tileset.tile_width as f32, tileset.tile_height as f32);
+            for id in 0..tileset.tile_count {
+                let (column, row) = (id % tileset.columns.max(1), id / tileset.columns.max(1));
+                let gid = tileset.first_gid as usize + id as usize;
+                let Some(frame) = gid.checked_sub(1).and_then(|index| frames.get_mut(index)) else {
+                    continue;
+                };
+                *frame = Rectangle::new(
+                    margin + column as f32 * (tile_width + spacing),
+                    (top as f32) + margin + row as f32 * (tile_height + spacing),
+                    tile_width,
+                    tile_height,
+                );
+            }
+            top += image.height;
+        }
+
+        let sprite = Sprite::from_rgba(width, height, pixels)?;
+        Ok(SpriteSheet::new(sprite, frames))
+    }
+
+    // diagonal flips aren't supported, those tiles draw unrotated
+    fn tile(&self, gid: u32) -> Option<Tile> {
+        let id = gid & GID_MASK;
+        if id == 0 {
+            return None;
+        }
+
+        // tilesets are in first gid order
+        let solid = self
+            .tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.first_gid <= id)
+            .and_then(|tileset| tileset.tiles.get(&(id - tileset.first_gid)))
+            .and_then(|properties| properties.get("solid"))
+            == Some(&Property::Bool(true));
+
+        Some(Tile {
+            index: id as usize - 1,
+            solid,
+            flip_x: gid & FLIP_X != 0,
+            flip_y: gid & FLIP_Y != 0,
+        })
+    }
+
+    // one entity per object, with a `MapObject` and its shape:
+    // rectangles and tile objects get a `Rectangle`, ellipses a `Circle` that fits inside,
+    // points a `Circle` with no radius, polygons and polylines a `Polygon`
+    pub fn spawn_objects(&self, world: &mut World) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        for layer in &self.layers {
+            let LayerKind::Objects(objects) = &layer.kind else {
+                continue;
+            };
+
+            for object in objects {
+                let mut entity = world.spawn(MapObject {
+                    id: object.id,
+                    name: object.name.clone(),
+                    class: object.class.clone(),
+                    layer: layer.name.clone(),
+                    visible: object.visible && layer.visible,
+                    rotation: object.rotation,
+                    gid: object.gid,
+                    properties: object.properties.clone(),
+                });
+                insert_shape(&mut entity, &object.shape);
+                entities.push(entity.id());
+            }
+        }
+        entities
+    }
+}
+
+fn insert_shape(entity: &mut EntityMut, shape: &Shape) {
+    match shape {
+        Shape::Rectangle(rectangle) => {
+            entity.insert(*rectangle);
+        }
+        Shape::Ellipse(bounds) => {
+            let radius = bounds.size.min_element() / 2.0;
+            entity.insert(Circle {
+                center: bounds.point + bounds.size / 2.0,
+                radius,
+            });
+        }
+        Shape::Point(point) => {
+            entity.insert(Circle {
+                center: *point,
+                radius: 0.0,
+            });
+        }
+        Shape::Polygon(points) => {
+            entity.insert(Polygon::new(points.clone()));
+        }
+        Shape::Polyline(points) => {
+            entity.insert(Polygon {
+                points: points.clone(),
+                closed: false,
+            });
+        }
+    }
+}
+
+// .tsx is xml and anything else json, relative to `base`
+fn external_tileset(source: &str, first_gid: u32, base: &Path) -> Result<Tileset, TiledError> {
+    let path = base.join(source);
+    let text = fs::read_to_string(&path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    if path.extension().is_some_and(|extension| extension == "tsx") {
+        xml::parse_tileset(&text, first_gid, base)
+    } else {
+        json::parse_tileset(&text, first_gid, base)
+    }
+}
+
+// csv, or base64 that might be compressed
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        Some("csv") | None => data
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .map_err(|_| invalid(format!("bad tile gid {:?}", gid)))
+            })
+            .collect(),
+        Some("base64") => {
+            let data: String = data.split_whitespace().collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|why| invalid(format!("bad base64 tile data: {}", why)))?;
+            let bytes = match compression {
+                None | Some("") => bytes,
+                Some("zlib") => decompress_to_vec_zlib(&bytes)
+                    .map_err(|why| invalid(format!("bad zlib tile data: {}", why)))?,
+                Some("gzip") => decompress_to_vec(gzip_body(&bytes)?)
+                    .map_err(|why| invalid(format!("bad gzip tile data: {}", why)))?,
+                Some(other) => return Err(invalid(format!("unsupported compression {}", other))),
+            };
+            if bytes.len() % 4 != 0 {
+                return Err(invalid(format!(
+                    "tile data is {} bytes, not a whole number of gids",
+                    bytes.len()
+                )));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes(gid.try_into().unwrap()))
+                .collect())
+        }
+        Some(other) => Err(invalid(format!("unsupported encoding {}", other))),
+    }
+}
+
+// a chunk's gids fill it exactly, one per tile
+fn whole_chunk(chunk: Chunk) -> Result<Chunk, TiledError> {
+    let tiles = chunk.width as u64 * chunk.height as u64;
+    if chunk.gids.len() as u64 != tiles {
+        return Err(invalid(format!(
+            "expected {} tile gids but found {}",
+            tiles,
+            chunk.gids.len()
+        )));
+    }
+    Ok(chunk)
+}
+
+// skips a gzip header, leaving the raw deflate stream
+fn gzip_body(bytes: &[u8]) -> Result<&[u8], TiledError> {
+    let truncated = || invalid("truncated gzip tile data");
+    if bytes.len() < 10 || bytes[..3] != [0x1f, 0x8b, 8] {
+        return Err(invalid("bad gzip tile data"));
+    }
+
+    let flags = bytes[3];
+    let mut rest = &bytes[10..];
+    if flags & 4 != 0 {
+        let len = u16::from_le_bytes(rest.get(..2).ok_or_else(truncated)?.try_into().unwrap());
+        rest = rest.get(2 + len as usize..).ok_or_else(truncated)?;
+    }
+    // file name, then comment, both zero terminated
+    for flag in [8, 16] {
+        if flags & flag != 0 {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(truncated)?;
+            rest = &rest[end + 1..];
+        }
+    }
+    if flags & 2 != 0 {
+        rest = rest.get(2..).ok_or_else(truncated)?;
+    }
+    Ok(rest)
+}
+
+// gid 0 means no tile, so a tileset's ids start from 1
+fn first_gid(first_gid: u32) -> Result<u32, TiledError> {
+    match first_gid {
+        0 => Err(invalid("tileset firstgid must be at least 1")),
+        first_gid => Ok(first_gid),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> TiledError {
+    TiledError::Invalid(message.into())
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(why) => write!(f, "couldn't read map: {}", why),
+            Self::Json(why) => write!(f, "couldn't parse map json: {}", why),
+            Self::Xml(why) => write!(f, "couldn't parse map xml: {}", why),
+            Self::Sprite(why) => write!(f, "couldn't load tileset: {}", why),
+            Self::Invalid(why) => write!(f, "invalid map: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<io::Error> for TiledError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(value: roxmltree::Error) -> Self {
+        Self::Xml(value)
+    }
+}
+
+impl From<SpriteError> for TiledError {
+    fn from(value: SpriteError) -> Self {
+        Self::Sprite(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Canvas;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/tiled")
+            .join(name)
+    }
+
+    // both fixtures describe the same map
+    fn check(map: TiledMap) {
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(
+            map.properties.get("music"),
+            Some(&Property::String("forest.ogg".to_string()))
+        );
+
+        let tilemap = map.tilemap().unwrap();
+        let ground = tilemap.layer("ground").unwrap();
+        assert_eq!(ground.get(0, 0), Some(&Tile::new(0)));
+        assert_eq!(ground.get(2, 0), None);
+        assert!(ground.get(1, 1).unwrap().flip_x);
+        assert!(tilemap.is_solid(1, 0) && !tilemap.is_solid(0, 0));
+
+        let mut canvas = Canvas::new(6, 4);
+        tilemap.draw(&mut canvas);
+        assert_eq!(canvas.get_pixel(1, 1), RED);
+        assert_eq!(canvas.get_pixel(3, 3), BLUE);
+        assert_eq!(canvas.get_pixel(5, 0), [0, 0, 0, 0]);
+
+        let mut world = World::new();
+        let entities = map.spawn_objects(&mut world);
+        assert_eq!(entities.len(), 4);
+
+        let door = world.entity(entities[0]);
+        let object = door.get::<MapObject>().unwrap();
+        assert_eq!(
+            (object.name.as_str(), object.class.as_str()),
+            ("door", "Door")
+        );
+        assert_eq!(object.layer, "objects");
+        assert_eq!(object.properties.get("locked"), Some(&Property::Bool(true)));
+        assert_eq!(
+            door.get::<Rectangle>(),
+            Some(&Rectangle::new(1.0, 0.0, 4.0, 2.0))
+        );
+
+        let ellipse = world.entity(entities[1]).get::<Circle>().unwrap();
+        assert_eq!((ellipse.center, ellipse.radius), (Vec2::new(4.0, 1.0), 1.0));
+        let spawn = world.entity(entities[2]).get::<Circle>().unwrap();
+        assert_eq!((spawn.center, spawn.radius), (Vec2::new(6.0, 3.0), 0.0));
+        let polygon = world.entity(entities[3]).get::<Polygon>().unwrap();
+        assert_eq!(polygon.points[2], Vec2::new(3.0, 2.0));
+        assert!(polygon.closed);
+    }
+
+    #[test]
+    fn tiled_xml() {
+        check(TiledMap::from_path(fixture("level.tmx")).unwrap());
+    }
+
+    #[test]
+    fn tiled_json() {
+        check(TiledMap::from_path(fixture("level.tmj")).unwrap());
+    }
+
+    #[test]
+    fn tiled_compressed() {
+        for name in ["zlib.tmx", "gzip.tmx"] {
+            let map = TiledMap::from_path(fixture(name)).unwrap();
+            let LayerKind::Tiles(chunks) = &map.layer("ground").unwrap().kind else {
+                panic!("{} should have a tile layer", name);
+            };
+            assert_eq!(chunks[0].gids, [1, 2, 0, 0, 2147483650, 1]);
+        }
+    }
+
+    #[test]
+    fn tiled_infinite() {
+        let map = TiledMap::from_path(fixture("infinite.tmx")).unwrap();
+        let tilemap = map.tilemap().unwrap();
+        let ground = tilemap.layer("ground").unwrap();
+        assert_eq!(ground.get(-1, 1), Some(&Tile::new(0)));
+        assert_eq!(ground.get(-1, 0), None);
+        assert!(ground.get(0, 1).unwrap().flip_x);
+        assert!(tilemap.is_solid(-2, 0) && tilemap.is_solid(1, 0));
+    }
+
+    #[test]
+    fn tiled_hidden_objects() {
+        let map = TiledMap::from_path(fixture("hidden.tmx")).unwrap();
+        let mut world = World::new();
+        let visible: Vec<_> = map
+            .spawn_objects(&mut world)
+            .into_iter()
+            .map(|entity| {
+                let object = world.entity(entity).get::<MapObject>().unwrap();
+                (object.name.as_str(), object.visible)
+            })
+            .collect();
+        assert_eq!(
+            visible,
+            [("shown", true), ("hidden", false), ("secret", false)]
+        );
+    }
+
+    #[test]
+    fn tiled_errors() {
+        assert!(matches!(
+            TiledMap::from_path(fixture("nothing_here.tmj")),
+            Err(TiledError::Io(_))
+        ));
+        assert!(matches!(
+            TiledMap::from_json("{ not json", ""),
+            Err(TiledError::Json(_))
+        ));
+        assert!(matches!(
+            TiledMap::from_xml("<tileset/>", ""),
+            Err(TiledError::Invalid(_))
+        ));
+        assert!(matches!(
+            TiledMap::from_path(fixture("zero_firstgid.tmx")),
+            Err(TiledError::Invalid(_))
+        ));
+        let json = r#"{"width": 1, "height": 1, "tilewidth": 2, "tileheight": 2,
+            "tilesets": [{"source": "tiles.tsx"}], "layers": []}"#;
+        assert!(matches!(
+            TiledMap::from_json(json, fixture("")),
+            Err(TiledError::Invalid(_))
+        ));
+        // gids that would need a frame each, far past any real tileset
+        for (first_gid, tile_count) in [(4_000_000_000u32, 1), (1, u32::MAX)] {
+            let xml = format!(
+                r#"<map width="1" height="1" tilewidth="2" tileheight="2">
+                    <tileset firstgid="{}" tilewidth="2" tileheight="2" tilecount="{}" columns="2">
+                        <image source="tiles.png"/>
+                    </tileset>
+                </map>"#,
+                first_gid, tile_count
+            );
+            let map = TiledMap::from_xml(&xml, fixture("")).unwrap();
+            assert!(matches!(map.tilemap(), Err(TiledError::Invalid(_))));
+        }
+        // three bytes, and five gids for a 3x2 layer
+        assert!(matches!(
+            decode_tiles("AAAA", Some("base64"), None),
+            Err(TiledError::Invalid(_))
+        ));
+        let xml = r#"<map width="3" height="2" tilewidth="2" tileheight="2">
+            <layer name="ground" width="3" height="2"><data encoding="csv">1,2,0,0,1</data></layer>
+        </map>"#;
+        assert!(matches!(
+            TiledMap::from_xml(xml, ""),
+            Err(TiledError::Invalid(_))
+        ));
+        assert!(matches!(
+            decode_tiles("AAAA", Some("base64"), Some("zstd")),
+            Err(TiledError::Invalid(_))
+        ));
+    }
+}
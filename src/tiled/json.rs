@@ -0,0 +1,255 @@
+// .tmj maps and .tsj tilesets
+// https://doc.mapeditor.org/en/stable/reference/json-map-format/
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::{
+    decode_tiles, external_tileset, first_gid, invalid, whole_chunk, Chunk, Layer, LayerKind,
+    Object, Properties, Property, Shape, TiledError, TiledMap, Tileset,
+};
+use crate::geometry::{Rectangle, Vec2};
+
+pub(super) fn map(source: &str, base: &Path) -> Result<TiledMap, TiledError> {
+    let map: Value = serde_json::from_str(source)?;
+
+    let tilesets = array(&map, "tilesets")
+        .iter()
+        .map(|tileset| {
+            let first_gid = first_gid(required(tileset, "firstgid")?)?;
+            match tileset.get("source").and_then(Value::as_str) {
+                Some(source) => external_tileset(source, first_gid, base),
+                None => self::tileset(tileset, first_gid, base),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut layers = Vec::new();
+    for layer in array(&map, "layers") {
+        self::layer(layer, Vec2::ZERO, true, &mut layers)?;
+    }
+
+    Ok(TiledMap {
+        width: required(&map, "width")?,
+        height: required(&map, "height")?,
+        tile_width: required(&map, "tilewidth")?,
+        tile_height: required(&map, "tileheight")?,
+        tilesets,
+        layers,
+        properties: properties(&map),
+    })
+}
+
+pub(super) fn parse_tileset(
+    source: &str,
+    first_gid: u32,
+    base: &Path,
+) -> Result<Tileset, TiledError> {
+    tileset(&serde_json::from_str(source)?, first_gid, base)
+}
+
+fn tileset(tileset: &Value, first_gid: u32, base: &Path) -> Result<Tileset, TiledError> {
+    let image = tileset
+        .get("image")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("tilesets made of separate images aren't supported"))?;
+
+    let tiles = array(tileset, "tiles")
+        .iter()
+        .map(|tile| (uint(tile, "id"), properties(tile)))
+        .collect();
+
+    Ok(Tileset {
+        first_gid,
+        name: string(tileset, "name"),
+        image: base.join(image),
+        tile_width: required(tileset, "tilewidth")?,
+        tile_height: required(tileset, "tileheight")?,
+        tile_count: required(tileset, "tilecount")?,
+        columns: required(tileset, "columns")?,
+        margin: uint(tileset, "margin"),
+        spacing: uint(tileset, "spacing"),
+        tiles,
+    })
+}
+
+// groups pass their offset and visibility down to their children
+fn layer(
+    layer: &Value,
+    offset: Vec2,
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), TiledError> {
+    let offset = offset + Vec2::new(float(layer, "offsetx"), float(layer, "offsety"));
+    let visible = visible
+        && layer
+            .get("visible")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+    let kind = match layer.get("type").and_then(Value::as_str) {
+        Some("tilelayer") => LayerKind::Tiles(chunks(layer)?),
+        Some("objectgroup") => LayerKind::Objects(
+            array(layer, "objects")
+                .iter()
+                .map(|object| self::object(object, offset))
+                .collect(),
+        ),
+        Some("group") => {
+            for child in array(layer, "layers") {
+                self::layer(child, offset, visible, layers)?;
+            }
+            return Ok(());
+        }
+        // image layers
+        _ => return Ok(()),
+    };
+
+    layers.push(Layer {
+        name: string(layer, "name"),
+        visible,
+        properties: properties(layer),
+        kind,
+    });
+    Ok(())
+}
+
+// infinite maps store chunks, finite ones a single `data`
+fn chunks(layer: &Value) -> Result<Vec<Chunk>, TiledError> {
+    let compression = layer.get("compression").and_then(Value::as_str);
+    let chunk = |chunk: &Value| -> Result<Chunk, TiledError> {
+        // base64 data is a string, plain data an array
+        let gids = match chunk.get("data") {
+            Some(Value::String(data)) => decode_tiles(data, Some("base64"), compression)?,
+            Some(Value::Array(data)) => data
+                .iter()
+                .map(|gid| gid.as_u64().map(|gid| gid as u32))
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid("tile gids should be numbers"))?,
+            _ => Vec::new(),
+        };
+
+        whole_chunk(Chunk {
+            x: int(chunk, "x"),
+            y: int(chunk, "y"),
+            width: uint(chunk, "width"),
+            height: uint(chunk, "height"),
+            gids,
+        })
+    };
+
+    match layer.get("chunks").and_then(Value::as_array) {
+        Some(chunks) => chunks.iter().map(chunk).collect(),
+        None => Ok(vec![chunk(layer)?]),
+    }
+}
+
+fn object(object: &Value, offset: Vec2) -> Object {
+    let point = offset + Vec2::new(float(object, "x"), float(object, "y"));
+    let size = Vec2::new(float(object, "width"), float(object, "height"));
+    let gid = object
+        .get("gid")
+        .and_then(Value::as_u64)
+        .map(|gid| gid as u32);
+    let points = |key| -> Vec<Vec2> {
+        array(object, key)
+            .iter()
+            .map(|offset| point + Vec2::new(float(offset, "x"), float(offset, "y")))
+            .collect()
+    };
+
+    let flag = |key| object.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let shape = if flag("point") {
+        Shape::Point(point)
+    } else if flag("ellipse") {
+        Shape::Ellipse(Rectangle { point, size })
+    } else if object.get("polygon").is_some() {
+        Shape::Polygon(points("polygon"))
+    } else if object.get("polyline").is_some() {
+        Shape::Polyline(points("polyline"))
+    } else if gid.is_some() {
+        // tile objects sit on their bottom left corner
+        Shape::Rectangle(Rectangle {
+            point: point - Vec2::new(0.0, size.y),
+            size,
+        })
+    } else {
+        Shape::Rectangle(Rectangle { point, size })
+    };
+
+    // `type` became `class` for a while
+    let class = match string(object, "class") {
+        class if class.is_empty() => string(object, "type"),
+        class => class,
+    };
+
+    Object {
+        id: uint(object, "id"),
+        name: string(object, "name"),
+        class,
+        visible: object
+            .get("visible")
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        rotation: float(object, "rotation"),
+        gid,
+        shape,
+        properties: properties(object),
+    }
+}
+
+fn properties(value: &Value) -> Properties {
+    array(value, "properties")
+        .iter()
+        .filter_map(|property| {
+            let kind = property.get("type").and_then(Value::as_str);
+            let value = match property.get("value")? {
+                Value::Bool(value) => Property::Bool(*value),
+                Value::Number(value) if kind != Some("float") && value.is_i64() => {
+                    Property::Int(value.as_i64()?)
+                }
+                Value::Number(value) => Property::Float(value.as_f64()?),
+                Value::String(value) => Property::String(value.clone()),
+                // class properties
+                _ => return None,
+            };
+            Some((string(property, "name"), value))
+        })
+        .collect()
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn string(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn float(value: &Value, key: &str) -> f32 {
+    value.get(key).and_then(Value::as_f64).unwrap_or(0.0) as f32
+}
+
+fn int(value: &Value, key: &str) -> i32 {
+    value.get(key).and_then(Value::as_i64).unwrap_or(0) as i32
+}
+
+fn uint(value: &Value, key: &str) -> u32 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+fn required(value: &Value, key: &str) -> Result<u32, TiledError> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(|value| value as u32)
+        .ok_or_else(|| invalid(format!("missing {}", key)))
+}
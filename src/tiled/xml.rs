@@ -0,0 +1,248 @@
+// .tmx maps and .tsx tilesets
+
+use std::{path::Path, str::FromStr};
+
+use roxmltree::{Document, Node};
+
+use super::{
+    decode_tiles, external_tileset, first_gid, invalid, whole_chunk, Chunk, Layer, LayerKind,
+    Object, Properties, Property, Shape, TiledError, TiledMap, Tileset,
+};
+use crate::geometry::{Rectangle, Vec2};
+
+pub(super) fn map(source: &str, base: &Path) -> Result<TiledMap, TiledError> {
+    let document = Document::parse(source)?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(invalid("expected a <map>"));
+    }
+
+    let tilesets = children(map, "tileset")
+        .map(|tileset| {
+            let first_gid = first_gid(required(tileset, "firstgid")?)?;
+            match tileset.attribute("source") {
+                Some(source) => external_tileset(source, first_gid, base),
+                None => self::tileset(tileset, first_gid, base),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut layers = Vec::new();
+    self::layers(map, Vec2::ZERO, true, &mut layers)?;
+
+    Ok(TiledMap {
+        width: required(map, "width")?,
+        height: required(map, "height")?,
+        tile_width: required(map, "tilewidth")?,
+        tile_height: required(map, "tileheight")?,
+        tilesets,
+        layers,
+        properties: properties(map),
+    })
+}
+
+pub(super) fn parse_tileset(
+    source: &str,
+    first_gid: u32,
+    base: &Path,
+) -> Result<Tileset, TiledError> {
+    let document = Document::parse(source)?;
+    tileset(document.root_element(), first_gid, base)
+}
+
+fn tileset(tileset: Node, first_gid: u32, base: &Path) -> Result<Tileset, TiledError> {
+    let image = children(tileset, "image")
+        .next()
+        .and_then(|image| image.attribute("source"))
+        .ok_or_else(|| invalid("tilesets made of separate images aren't supported"))?;
+
+    let tiles = children(tileset, "tile")
+        .map(|tile| (attribute(tile, "id").unwrap_or(0), properties(tile)))
+        .collect();
+
+    Ok(Tileset {
+        first_gid,
+        name: tileset.attribute("name").unwrap_or_default().to_string(),
+        image: base.join(image),
+        tile_width: required(tileset, "tilewidth")?,
+        tile_height: required(tileset, "tileheight")?,
+        tile_count: required(tileset, "tilecount")?,
+        columns: required(tileset, "columns")?,
+        margin: attribute(tileset, "margin").unwrap_or(0),
+        spacing: attribute(tileset, "spacing").unwrap_or(0),
+        tiles,
+    })
+}
+
+// every layer inside `parent`, in order,
+// groups pass their offset and visibility down to their children
+fn layers(
+    parent: Node,
+    offset: Vec2,
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), TiledError> {
+    for layer in parent.children().filter(Node::is_element) {
+        let offset = offset
+            + Vec2::new(
+                attribute(layer, "offsetx").unwrap_or(0.0),
+                attribute(layer, "offsety").unwrap_or(0.0),
+            );
+        let visible = visible && layer.attribute("visible") != Some("0");
+
+        let kind = match layer.tag_name().name() {
+            "layer" => LayerKind::Tiles(chunks(layer)?),
+            "objectgroup" => LayerKind::Objects(
+                children(layer, "object")
+                    .map(|object| self::object(object, offset))
+                    .collect(),
+            ),
+            "group" => {
+                self::layers(layer, offset, visible, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+
+        layers.push(Layer {
+            name: layer.attribute("name").unwrap_or_default().to_string(),
+            visible,
+            properties: properties(layer),
+            kind,
+        });
+    }
+    Ok(())
+}
+
+// infinite maps store chunks, finite ones put the tiles straight in <data>
+fn chunks(layer: Node) -> Result<Vec<Chunk>, TiledError> {
+    let Some(data) = children(layer, "data").next() else {
+        return Ok(Vec::new());
+    };
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+
+    let chunk = |node: Node| -> Result<Chunk, TiledError> {
+        // tiles as <tile gid=".."/> elements when there's no encoding
+        let gids = if encoding.is_none() && children(node, "tile").next().is_some() {
+            children(node, "tile")
+                .map(|tile| attribute(tile, "gid").unwrap_or(0))
+                .collect()
+        } else {
+            decode_tiles(node.text().unwrap_or_default(), encoding, compression)?
+        };
+
+        Ok(Chunk {
+            x: attribute(node, "x").unwrap_or(0),
+            y: attribute(node, "y").unwrap_or(0),
+            width: attribute(node, "width").unwrap_or(0),
+            height: attribute(node, "height").unwrap_or(0),
+            gids,
+        })
+    };
+
+    if children(data, "chunk").next().is_some() {
+        children(data, "chunk")
+            .map(|node| whole_chunk(chunk(node)?))
+            .collect()
+    } else {
+        let mut single = chunk(data)?;
+        single.width = required(layer, "width")?;
+        single.height = required(layer, "height")?;
+        Ok(vec![whole_chunk(single)?])
+    }
+}
+
+fn object(object: Node, offset: Vec2) -> Object {
+    let point = offset
+        + Vec2::new(
+            attribute(object, "x").unwrap_or(0.0),
+            attribute(object, "y").unwrap_or(0.0),
+        );
+    let size = Vec2::new(
+        attribute(object, "width").unwrap_or(0.0),
+        attribute(object, "height").unwrap_or(0.0),
+    );
+    let gid = attribute(object, "gid");
+
+    // "x,y x,y .." relative to the object
+    let points = |node: Node| -> Vec<Vec2> {
+        node.attribute("points")
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|pair| {
+                let (x, y) = pair.split_once(',')?;
+                Some(point + Vec2::new(x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect()
+    };
+
+    let child = object
+        .children()
+        .find(|child| child.is_element() && !child.has_tag_name("properties"));
+    let shape = match child.map(|child| (child.tag_name().name(), child)) {
+        Some(("point", _)) => Shape::Point(point),
+        Some(("ellipse", _)) => Shape::Ellipse(Rectangle { point, size }),
+        Some(("polygon", child)) => Shape::Polygon(points(child)),
+        Some(("polyline", child)) => Shape::Polyline(points(child)),
+        // tile objects sit on their bottom left corner
+        _ if gid.is_some() => Shape::Rectangle(Rectangle {
+            point: point - Vec2::new(0.0, size.y),
+            size,
+        }),
+        _ => Shape::Rectangle(Rectangle { point, size }),
+    };
+
+    // `type` became `class` for a while
+    let class = object
+        .attribute("class")
+        .or_else(|| object.attribute("type"))
+        .unwrap_or_default();
+
+    Object {
+        id: attribute(object, "id").unwrap_or(0),
+        name: object.attribute("name").unwrap_or_default().to_string(),
+        class: class.to_string(),
+        visible: object.attribute("visible") != Some("0"),
+        rotation: attribute(object, "rotation").unwrap_or(0.0),
+        gid,
+        shape,
+        properties: properties(object),
+    }
+}
+
+fn properties(node: Node) -> Properties {
+    children(node, "properties")
+        .flat_map(|properties| children(properties, "property"))
+        .filter_map(|property| {
+            // multiline strings go in the element's text
+            let value = property.attribute("value").or_else(|| property.text())?;
+            let value = match property.attribute("type") {
+                Some("bool") => Property::Bool(value == "true"),
+                Some("int") | Some("object") => Property::Int(value.parse().ok()?),
+                Some("float") => Property::Float(value.parse().ok()?),
+                // class properties
+                Some("class") => return None,
+                _ => Property::String(value.to_string()),
+            };
+            Some((property.attribute("name")?.to_string(), value))
+        })
+        .collect()
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Option<T> {
+    node.attribute(name)?.parse().ok()
+}
+
+fn required(node: Node, name: &str) -> Result<u32, TiledError> {
+    attribute(node, name)
+        .ok_or_else(|| invalid(format!("<{}> is missing {}", node.tag_name().name(), name)))
+}
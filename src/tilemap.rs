@@ -0,0 +1,330 @@
+// grids of sprite sheet frames, stored in chunks so maps can be big and sparse
+
+use std::collections::HashMap;
+
+use bevy_ecs::{
+    prelude::Component,
+    system::{Query, Res, ResMut, Resource},
+};
+
+use crate::{
+    app::{PandaBuilder, Plugin},
+    camera::Camera,
+    geometry::{Rectangle, Vec2},
+    sheet::SpriteSheet,
+    sprite::DrawParams,
+    Canvas,
+};
+
+// tiles along each side of a chunk
+pub const CHUNK_SIZE: i32 = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    // frame in the tileset
+    pub index: usize,
+    pub solid: bool,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Tile {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn draw_params(&self) -> DrawParams {
+        DrawParams {
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+            ..DrawParams::default()
+        }
+    }
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    chunks: HashMap<(i32, i32), Vec<Option<Tile>>>,
+}
+
+impl TileLayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        let (chunk, index) = split(x, y);
+        self.chunks.get(&chunk)?[index].as_ref()
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        let (chunk, index) = split(x, y);
+        let chunk = self
+            .chunks
+            .entry(chunk)
+            .or_insert_with(|| vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize]);
+        chunk[index] = Some(tile);
+    }
+
+    pub fn remove(&mut self, x: i32, y: i32) -> Option<Tile> {
+        let (chunk, index) = split(x, y);
+        self.chunks.get_mut(&chunk)?[index].take()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    // every tile with its position, in no particular order
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), &Tile)> {
+        self.chunks.iter().flat_map(|(&(chunk_x, chunk_y), tiles)| {
+            tiles.iter().enumerate().filter_map(move |(i, tile)| {
+                let x = chunk_x * CHUNK_SIZE + i as i32 % CHUNK_SIZE;
+                let y = chunk_y * CHUNK_SIZE + i as i32 / CHUNK_SIZE;
+                tile.as_ref().map(|tile| ((x, y), tile))
+            })
+        })
+    }
+}
+
+// which chunk a tile is in, and where in it
+fn split(x: i32, y: i32) -> ((i32, i32), usize) {
+    let chunk = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+    let index = y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE);
+    (chunk, index as usize)
+}
+
+// works as a resource for the one level, or a component for several
+#[derive(Resource, Component)]
+pub struct Tilemap {
+    pub tileset: SpriteSheet,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    // where the top left of tile (0, 0) goes
+    pub position: Vec2,
+    // drawn first to last
+    pub layers: Vec<TileLayer>,
+}
+
+impl Tilemap {
+    pub fn new(tileset: SpriteSheet, tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            tileset,
+            tile_width,
+            tile_height,
+            position: Vec2::ZERO,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn add_layer(&mut self, name: &str) -> &mut TileLayer {
+        self.layers.push(TileLayer::new(name));
+        self.layers.last_mut().unwrap()
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    fn tile_size(&self) -> Vec2 {
+        Vec2::new(self.tile_width as f32, self.tile_height as f32)
+    }
+
+    // the tile under a point
+    pub fn tile_at(&self, point: Vec2) -> (i32, i32) {
+        let tile = ((point - self.position) / self.tile_size()).floor();
+        (tile.x as i32, tile.y as i32)
+    }
+
+    pub fn tile_rectangle(&self, x: i32, y: i32) -> Rectangle {
+        let point = self.position + Vec2::new(x as f32, y as f32) * self.tile_size();
+        Rectangle {
+            point,
+            size: self.tile_size(),
+        }
+    }
+
+    // solid in any layer, hidden ones included
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.layers
+            .iter()
+            .any(|layer| layer.get(x, y).is_some_and(|tile| tile.solid))
+    }
+
+    pub fn is_solid_at(&self, point: Vec2) -> bool {
+        let (x, y) = self.tile_at(point);
+        self.is_solid(x, y)
+    }
+
+    // first and one-past-last tile touching `area`
+    pub fn tile_range(&self, area: &Rectangle) -> ((i32, i32), (i32, i32)) {
+        let start = ((area.point - self.position) / self.tile_size()).floor();
+        let end = ((area.point + area.size - self.position) / self.tile_size()).ceil();
+        (
+            (start.x as i32, start.y as i32),
+            (end.x as i32, end.y as i32),
+        )
+    }
+
+    // only the tiles that land on the canvas
+    pub fn draw(&self, canvas: &mut Canvas) {
+        let viewport = Rectangle::new(0.0, 0.0, canvas.width() as f32, canvas.height() as f32);
+        self.visible_tiles(&viewport, |point, tile| {
+            self.tileset
+                .draw_with(canvas, tile.index, &point, &tile.draw_params());
+        });
+    }
+
+    // calls `draw` with the top left of every tile in a visible layer touching `area`
+    pub(crate) fn visible_tiles(&self, area: &Rectangle, mut draw: impl FnMut(Vec2, &Tile)) {
+        let ((left, top), (right, bottom)) = self.tile_range(area);
+        if self.tile_width == 0 || self.tile_height == 0 || left >= right || top >= bottom {
+            return;
+        }
+
+        // visit chunks rather than tiles so empty parts of the map cost nothing
+        let (chunk_left, chunk_top) = split(left, top).0;
+        let (chunk_right, chunk_bottom) = split(right - 1, bottom - 1).0;
+
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            for chunk_y in chunk_top..=chunk_bottom {
+                for chunk_x in chunk_left..=chunk_right {
+                    let Some(tiles) = layer.chunks.get(&(chunk_x, chunk_y)) else {
+                        continue;
+                    };
+
+                    for (i, tile) in tiles.iter().enumerate() {
+                        let Some(tile) = tile else {
+                            continue;
+                        };
+                        let x = chunk_x * CHUNK_SIZE + i as i32 % CHUNK_SIZE;
+                        let y = chunk_y * CHUNK_SIZE + i as i32 / CHUNK_SIZE;
+                        if x < left || x >= right || y < top || y >= bottom {
+                            continue;
+                        }
+
+                        draw(self.tile_rectangle(x, y).point, tile);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// adds `draw_tilemaps` to the render stage
+pub struct TilemapPlugin;
+
+impl Plugin for TilemapPlugin {
+    fn build(&self, builder: &mut PandaBuilder) {
+        builder.add_render_system(draw_tilemaps);
+    }
+}
+
+// draws the `Tilemap` resource, then every `Tilemap` component, through the `Camera` if there is one
+pub fn draw_tilemaps(
+    mut canvas: ResMut<Canvas>,
+    camera: Option<Res<Camera>>,
+    tilemap: Option<Res<Tilemap>>,
+    tilemaps: Query<&Tilemap>,
+) {
+    let tilemaps = tilemap.as_deref().into_iter().chain(&tilemaps);
+    match camera {
+        Some(camera) => {
+            let mut view = camera.view(&mut canvas);
+            tilemaps.for_each(|tilemap| view.draw_tilemap(tilemap));
+        }
+        None => tilemaps.for_each(|tilemap| tilemap.draw(&mut canvas)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sheet::Grid, sprite::Sprite};
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    // two 2x2 tiles, red then blue
+    fn tilemap() -> Tilemap {
+        let pixels = [RED, RED, BLUE, BLUE, RED, RED, BLUE, BLUE].concat();
+        let sprite = Sprite::from_rgba(4, 2, pixels).unwrap();
+        Tilemap::new(SpriteSheet::from_grid(sprite, Grid::new(2, 2)), 2, 2)
+    }
+
+    #[test]
+    fn tilemap_chunks() {
+        let mut layer = TileLayer::new("ground");
+        layer.set(-1, -1, Tile::new(1));
+        layer.set(40, 3, Tile::new(2));
+        assert_eq!(layer.get(-1, -1), Some(&Tile::new(1)));
+        assert_eq!(layer.get(40, 3), Some(&Tile::new(2)));
+        assert_eq!(layer.get(0, 0), None);
+        assert_eq!(layer.chunks.len(), 2);
+
+        let mut tiles: Vec<_> = layer.tiles().map(|(position, _)| position).collect();
+        tiles.sort();
+        assert_eq!(tiles, [(-1, -1), (40, 3)]);
+
+        assert_eq!(layer.remove(40, 3), Some(Tile::new(2)));
+        assert_eq!(layer.get(40, 3), None);
+    }
+
+    #[test]
+    fn tilemap_solid() {
+        let mut tilemap = tilemap();
+        tilemap.position = Vec2::new(10.0, 0.0);
+        let wall = Tile {
+            solid: true,
+            ..Tile::new(0)
+        };
+        tilemap.add_layer("walls").set(1, 0, wall);
+
+        assert_eq!(tilemap.tile_at(Vec2::new(9.0, 3.0)), (-1, 1));
+        assert!(tilemap.is_solid_at(Vec2::new(12.5, 1.0)));
+        assert!(!tilemap.is_solid_at(Vec2::new(11.5, 1.0)));
+    }
+
+    #[test]
+    fn tilemap_draw() {
+        let mut tilemap = tilemap();
+        tilemap.position = Vec2::new(-1.0, 0.0);
+        let layer = tilemap.add_layer("ground");
+        layer.set(0, 0, Tile::new(0));
+        layer.set(
+            1,
+            0,
+            Tile {
+                flip_x: true,
+                ..Tile::new(1)
+            },
+        );
+        layer.set(1000, 1000, Tile::new(0));
+
+        let viewport = Rectangle::new(0.0, 0.0, 3.0, 2.0);
+        assert_eq!(tilemap.tile_range(&viewport), ((0, 0), (2, 1)));
+
+        let mut canvas = Canvas::new(3, 2);
+        tilemap.draw(&mut canvas);
+        assert_eq!(canvas.get_pixel(0, 0), RED);
+        assert_eq!(canvas.get_pixel(1, 1), BLUE);
+        assert_eq!(canvas.get_pixel(2, 0), BLUE);
+
+        tilemap.layers[0].visible = false;
+        let mut canvas = Canvas::new(3, 2);
+        tilemap.draw(&mut canvas);
+        assert_eq!(canvas.get_pixel(0, 0), [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::ops::Deref;
+
+use bevy_ecs::system::Resource;
+
+use crate::Duration;
+
+// how much the fps follows the latest frame, the rest is the older frames
+const FPS_SMOOTHING: f32 = 0.1;
+
+// derefs to this frame's delta, scaled and clamped,
+// so `time.as_secs_f32()` is all most systems need
+#[derive(Resource, Clone, Debug)]
+pub struct Time {
+    // 0.5 for half speed slow motion, 2.0 for double speed,
+    // nan and infinity stop time like `paused`
+    pub scale: f32,
+    // stops scaled time, `raw_delta` and `raw_elapsed` keep going
+    pub paused: bool,
+    // longer frames count as this long, so a hiccup doesn't send things flying
+    pub max_delta: Duration,
+    delta: Duration,
+    raw_delta: Duration,
+    elapsed: Duration,
+    raw_elapsed: Duration,
+    frame: u64,
+    // smoothed seconds per frame
+    frame_time: f32,
+}
+
+impl Time {
+    pub(crate) fn new() -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            max_delta: Duration::from_millis(250),
+            delta: Duration::ZERO,
+            raw_delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            raw_elapsed: Duration::ZERO,
+            frame: 0,
+            frame_time: 0.0,
+        }
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    // the real time since the last frame, ignoring scale, pause and `max_delta`
+    pub fn raw_delta(&self) -> Duration {
+        self.raw_delta
+    }
+
+    // all the scaled time so far
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn raw_elapsed(&self) -> Duration {
+        self.raw_elapsed
+    }
+
+    // how many frames have started, 1 during the first
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // frames per second, averaged over the last few frames
+    pub fn fps(&self) -> f32 {
+        match self.frame_time > 0.0 {
+            true => 1.0 / self.frame_time,
+            false => 0.0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, raw_delta: Duration) {
+        self.raw_delta = raw_delta;
+        self.raw_elapsed += raw_delta;
+        self.frame += 1;
+
+        self.delta = match self.paused {
+            true => Duration::ZERO,
+            false if self.scale == 1.0 => raw_delta.min(self.max_delta),
+            false if !self.scale.is_finite() => Duration::ZERO,
+            false => {
+                let seconds = raw_delta.min(self.max_delta).as_secs_f64();
+                Duration::try_from_secs_f64(seconds * self.scale.max(0.0) as f64)
+                    .unwrap_or(Duration::MAX)
+            }
+        };
+        self.elapsed = self.elapsed.saturating_add(self.delta);
+
+        let seconds = raw_delta.as_secs_f32();
+        if seconds > 0.0 {
+            self.frame_time = match self.frame_time > 0.0 {
+                true => self.frame_time + (seconds - self.frame_time) * FPS_SMOOTHING,
+                false => seconds,
+            };
+        }
+    }
+}
+
+impl Deref for Time {
+    type Target = Duration;
+    fn deref(&self) -> &Self::Target {
+        &self.delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    #[test]
+    fn time_scale_and_pause() {
+        let mut time = Time::new();
+        time.update(FRAME);
+        time.scale = 0.5;
+        time.update(FRAME);
+        assert_eq!(time.delta(), Duration::from_millis(10));
+        assert_eq!(time.elapsed(), Duration::from_millis(30));
+
+        time.paused = true;
+        time.update(FRAME);
+        assert_eq!(*time, Duration::ZERO);
+        assert_eq!(time.elapsed(), Duration::from_millis(30));
+        assert_eq!(time.raw_delta(), FRAME);
+        assert_eq!(time.raw_elapsed(), Duration::from_millis(60));
+        assert_eq!(time.frame(), 3);
+    }
+
+    #[test]
+    fn time_odd_scales() {
+        let mut time = Time::new();
+        for scale in [f32::INFINITY, f32::NAN, -1.0] {
+            time.scale = scale;
+            time.update(FRAME);
+            assert_eq!(time.delta(), Duration::ZERO);
+        }
+
+        // too big for a duration
+        time.scale = f32::MAX;
+        time.update(FRAME);
+        time.update(FRAME);
+        assert_eq!(time.elapsed(), Duration::MAX);
+    }
+
+    #[test]
+    fn time_clamp_and_fps() {
+        let mut time = Time::new();
+        assert_eq!(time.fps(), 0.0);
+        for _ in 0..100 {
+            time.update(FRAME);
+        }
+        assert!((time.fps() - 50.0).abs() < 0.01);
+
+        // a long frame gets clamped, and the fps settles back after it
+        time.update(Duration::from_secs(3));
+        assert_eq!(time.delta(), time.max_delta);
+        assert_eq!(time.raw_delta(), Duration::from_secs(3));
+        assert!(time.fps() < 50.0);
+        for _ in 0..100 {
+            time.update(FRAME);
+        }
+        assert!((time.fps() - 50.0).abs() < 0.1);
+    }
+}